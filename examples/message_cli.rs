@@ -22,7 +22,6 @@
 
 use anthropic_api::{messages::*, Credentials};
 use std::io::{stdin, stdout, Write};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() {
@@ -35,38 +34,24 @@ async fn main() {
     // Load .env file containing ANTHROPIC_API_KEY
     let credentials = Credentials::from_env();
 
-    let mut messages = vec![Message {
-        role: MessageRole::User,
-        content: MessageContent::Text(
-            "You are a helpful AI assistant. Please introduce yourself briefly.".to_string(),
-        ),
-    }];
+    // The builder holds the conversation history; each turn is appended to it.
+    let mut conversation = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 2048)
+        .credentials(credentials)
+        .user_text("You are a helpful AI assistant. Please introduce yourself briefly.");
+    // Uncomment this to enable thinking
+    // conversation = conversation.enable_thinking(1024);
 
     // Create initial message request
-    let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", messages.clone(), 2048)
-        .credentials(credentials.clone())
-        // Uncomment this to enable thinking
-        // .thinking(Thinking {
-        //     thinking_type: ThinkingType::Enabled,
-        //     budget_tokens: 1024,
-        // })
-        .create()
-        .await
-        .unwrap();
+    let response = conversation.clone().create().await.unwrap();
 
     // Print assistant's response
     // Iterate through all content blocks in the response
-    let mut assistant_response = String::new();
     for content in &response.content {
         match content {
-            ResponseContentBlock::Text { text } => {
+            ResponseContentBlock::Text { text, .. } => {
                 println!("Assistant: {}", text.trim());
-                assistant_response.push_str(text);
             }
-            ResponseContentBlock::Thinking {
-                signature,
-                thinking,
-            } => {
+            ResponseContentBlock::Thinking { thinking, .. } => {
                 println!("Assistant: [Thinking content] {}", thinking);
             }
             _ => {
@@ -76,12 +61,7 @@ async fn main() {
     }
 
     // Add the assistant's response to the message history
-    if !assistant_response.is_empty() {
-        messages.push(Message {
-            role: MessageRole::Assistant,
-            content: MessageContent::Text(assistant_response),
-        });
-    }
+    conversation = conversation.assistant_text(response.text());
 
     // Start conversation loop
     loop {
@@ -91,32 +71,13 @@ async fn main() {
         let mut user_input = String::new();
         stdin().read_line(&mut user_input).unwrap();
 
-        // Add user message
-        messages.push(Message {
-            role: MessageRole::User,
-            content: MessageContent::Text(user_input),
-        });
-
-        // Send message request
-        let response =
-            MessagesResponse::builder("claude-3-7-sonnet-20250219", messages.clone(), 1024)
-                .credentials(credentials.clone())
-                .create()
-                .await
-                .unwrap();
+        // Add user message and send the conversation so far
+        conversation = conversation.user_text(user_input);
+        let response = conversation.clone().create().await.unwrap();
 
-        // Print assistant's response
-        if let Some(content) = response.content.first() {
-            match content {
-                ResponseContentBlock::Text { text } => {
-                    println!("Assistant: {}", text.trim());
-                    messages.push(Message {
-                        role: MessageRole::Assistant,
-                        content: MessageContent::Text(text.clone()),
-                    });
-                }
-                _ => {}
-            }
-        }
+        // Print assistant's response and add it to the history
+        let text = response.text();
+        println!("Assistant: {}", text.trim());
+        conversation = conversation.assistant_text(text);
     }
 }
@@ -28,12 +28,9 @@ use std::io::{stdin, stdout, Write};
 async fn main() {
     let credentials = Credentials::from_env();
 
-    let mut messages = vec![Message {
-        role: MessageRole::User,
-        content: MessageContent::Text(
-            "You are a helpful AI assistant. Please introduce yourself briefly.".to_string(),
-        ),
-    }];
+    let mut messages = vec![Message::user(
+        "You are a helpful AI assistant. Please introduce yourself briefly.",
+    )];
 
     // Create initial message request with streaming
     let mut stream =
@@ -48,16 +45,20 @@ async fn main() {
     stdout().flush().unwrap();
     while let Some(event) = stream.recv().await {
         match event {
-            StreamEvent::ContentBlockDelta { delta, .. } => {
-                if let ContentBlockDelta::Text { text } = delta {
-                    print!("{}", text);
-                    stdout().flush().unwrap();
-                }
+            Ok(StreamEvent::ContentBlockDelta {
+                delta: ContentBlockDelta::Text { text },
+                ..
+            }) => {
+                print!("{}", text);
+                stdout().flush().unwrap();
             }
-            StreamEvent::MessageStop => {
+            Ok(StreamEvent::MessageStop) => {
                 println!();
             }
-            _ => {}
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("\nStream error: {}", e);
+            }
         }
     }
 
@@ -70,10 +71,7 @@ async fn main() {
         stdin().read_line(&mut user_input).unwrap();
 
         // Add user message
-        messages.push(Message {
-            role: MessageRole::User,
-            content: MessageContent::Text(user_input),
-        });
+        messages.push(Message::user(user_input));
 
         // Send message request with streaming
         let mut stream =
@@ -86,27 +84,33 @@ async fn main() {
         // Print assistant's streaming response and store the text
         print!("\nAssistant: ");
         stdout().flush().unwrap();
-        let mut full_response = String::new();
+        let mut accumulator = StreamAccumulator::default();
         while let Some(event) = stream.recv().await {
+            if let Ok(event) = &event {
+                accumulator.push(event);
+            }
             match event {
-                StreamEvent::ContentBlockDelta { delta, .. } => {
-                    if let ContentBlockDelta::Text { text } = delta {
-                        print!("{}", text);
-                        stdout().flush().unwrap();
-                        full_response.push_str(&text);
-                    }
+                Ok(StreamEvent::ContentBlockDelta {
+                    delta: ContentBlockDelta::Text { text },
+                    ..
+                }) => {
+                    print!("{}", text);
+                    stdout().flush().unwrap();
                 }
-                StreamEvent::MessageStop => {
+                Ok(StreamEvent::MessageStop) => {
                     println!();
                 }
-                _ => {}
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("\nStream error: {}", e);
+                }
             }
         }
 
         // Add assistant's complete response to messages
-        messages.push(Message {
-            role: MessageRole::Assistant,
-            content: MessageContent::Text(full_response),
-        });
+        match accumulator.into_message() {
+            Ok(message) => messages.push(message),
+            Err(e) => eprintln!("\nIncomplete response: {}", e),
+        }
     }
 }
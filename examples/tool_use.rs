@@ -7,8 +7,7 @@
 //!
 //! - Defines a calculator tool with a JSON schema
 //! - Allows Claude to use the tool when appropriate
-//! - Maintains conversation history for context
-//! - Simple command-line interface for user input
+//! - Runs the tool and sends the result back until Claude gives a final answer
 //!
 //! ## Usage
 //!
@@ -28,10 +27,10 @@ async fn main() {
     let credentials = Credentials::from_env();
 
     // Define a calculator tool
-    let calculator_tool = Tool {
-        name: "calculator".to_string(),
-        description: "A calculator that can perform basic arithmetic operations".to_string(),
-        input_schema: json!({
+    let calculator_tool = Tool::new(
+        "calculator",
+        "A calculator that can perform basic arithmetic operations",
+        json!({
             "type": "object",
             "properties": {
                 "operation": {
@@ -47,48 +46,42 @@ async fn main() {
             },
             "required": ["operation", "operands"]
         }),
-    };
+    )
+    .unwrap();
+
+    // Run the calculator whenever Claude asks for it
+    let runner = ToolRunner::new().tool(calculator_tool, |input| async move {
+        let operands: Vec<f64> = input["operands"]
+            .as_array()
+            .map(|operands| operands.iter().filter_map(|n| n.as_f64()).collect())
+            .unwrap_or_default();
+        let [a, b] = operands[..] else {
+            anyhow::bail!("expected two operands");
+        };
+        let result = match input["operation"].as_str() {
+            Some("add") => a + b,
+            Some("subtract") => a - b,
+            Some("multiply") => a * b,
+            Some("divide") => a / b,
+            _ => anyhow::bail!("unknown operation"),
+        };
+        println!("Claude used the calculator: {} -> {}", input, result);
+        Ok(result.to_string())
+    });
 
     let content =
         "You are a helpful AI assistant. Please calculate 15 + 27 using the calculator tool.";
-    let mut messages = vec![Message {
-        role: MessageRole::User,
-        content: MessageContent::Text(content.to_string()),
-    }];
-
-    println!("Claude: {}", content);
+    println!("User: {}", content);
 
-    // Create message request with tool
-    let response = MessagesResponse::builder("claude-3-7-sonnet-20250219", messages.clone(), 1024)
-        .credentials(credentials.clone())
-        .tools(vec![calculator_tool.clone()])
-        .tool_choice(ToolChoice::Any)
-        .create()
+    // Send the request and keep answering tool uses until Claude gives a final answer
+    let response = runner
+        .run(
+            MessagesResponse::builder("claude-3-7-sonnet-20250219", [], 1024)
+                .credentials(credentials)
+                .user_text(content),
+        )
         .await
         .unwrap();
 
-    // Print assistant's response and tool usage
-    for content in response.content {
-        match content {
-            ResponseContentBlock::Text { text } => {
-                println!("Assistant: {}", text.trim());
-                messages.push(Message {
-                    role: MessageRole::Assistant,
-                    content: MessageContent::Text(text),
-                });
-            }
-            ResponseContentBlock::ToolUse { name, input, .. } => {
-                println!("Claude decided to use the tool: {}: {}", name, input);
-            }
-            ResponseContentBlock::Thinking {
-                signature,
-                thinking,
-            } => {
-                println!("Claude {} is thinking: {}", signature, thinking);
-            }
-            ResponseContentBlock::RedactedThinking { data } => {
-                println!("Claude is thinking: {}", data);
-            }
-        }
-    }
+    println!("Claude: {}", response.text().trim());
 }
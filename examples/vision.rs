@@ -0,0 +1,49 @@
+//! # Vision Example
+//!
+//! This example demonstrates how to send an image to Claude.
+//! It loads a local image file and asks Claude to describe it.
+//!
+//! ## Features
+//!
+//! - Loads an image from disk as a base64 image source
+//! - Combines the image and a question in a single user message
+//! - Prints Claude's description of the image
+//!
+//! ## Usage
+//!
+//! Run this example with the path to a JPEG, PNG, GIF, or WebP image:
+//!
+//! ```bash
+//! cargo run --example vision -- path/to/image.png
+//! ```
+//!
+//! Make sure you have set the `ANTHROPIC_API_KEY` environment variable.
+
+use anthropic_api::{messages::*, Credentials};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let credentials = Credentials::from_env();
+
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("usage: cargo run --example vision -- <image>")?;
+
+    // Load the image; the media type is inferred from the file extension
+    let image = ImageSource::from_file(&path)?;
+
+    // Images are best placed before the question about them
+    let content = ContentBuilder::new()
+        .image(image)
+        .text("Describe this image in a few sentences.");
+
+    let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+        .credentials(credentials)
+        .message(Message::user(content))
+        .create()
+        .await?;
+
+    println!("Claude: {}", response.text().trim());
+
+    Ok(())
+}
@@ -1,13 +1,15 @@
 //! # API Keys Admin API
 //!
 //! This module provides a Rust interface to Anthropic's Admin API for managing API keys, which allows you to
-//! list, get, and update API keys.
+//! list, create, get, update, and archive API keys.
 //!
 //! ## Key Features
 //!
 //! - List all API keys with pagination and filtering support
+//! - Create new API keys
 //! - Get detailed information about a specific API key
 //! - Update API key properties like name and status
+//! - Archive API keys that are no longer needed
 //!
 //! ## Basic Usage
 //!
@@ -40,10 +42,17 @@
 //! }
 //! ```
 
-use crate::{anthropic_request_json, ApiResponseOrError, Credentials};
+use crate::pagination::{impl_paginated, validate_limit};
+use crate::timestamp::{to_system_time, Timestamp};
+use crate::{anthropic_request_json, builder_error, ApiResponseOrError, Credentials};
+#[cfg(feature = "runtime")]
+use crate::{error_channel, pagination::Paginated};
 use derive_builder::Builder;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::Receiver;
 
 /// Status of an API key
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -57,8 +66,19 @@ pub enum ApiKeyStatus {
     Archived,
 }
 
+impl ApiKeyStatus {
+    /// Returns the value used by the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyStatus::Active => "active",
+            ApiKeyStatus::Inactive => "inactive",
+            ApiKeyStatus::Archived => "archived",
+        }
+    }
+}
+
 /// Information about the creator of an API key
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct ApiKeyCreator {
     /// ID of the creator
     pub id: String,
@@ -68,14 +88,14 @@ pub struct ApiKeyCreator {
 }
 
 /// An API key available through the Anthropic Admin API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct ApiKey {
     /// Unique API key identifier
     pub id: String,
     /// Name of the API key
     pub name: String,
     /// RFC 3339 datetime string representing the time at which the API key was created
-    pub created_at: String,
+    pub created_at: Timestamp,
     /// Information about who created the API key
     pub created_by: ApiKeyCreator,
     /// Partially redacted hint for the API key
@@ -87,10 +107,15 @@ pub struct ApiKey {
     pub key_type: String,
     /// ID of the Workspace associated with the API key, or null if the API key belongs to the default Workspace
     pub workspace_id: Option<String>,
+    /// The full secret value of the API key.
+    ///
+    /// Only returned once, when the key is created; it is `None` in get and list responses.
+    #[serde(default)]
+    pub key: Option<String>,
 }
 
 /// Response from the List API Keys API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct ApiKeyList {
     /// List of available API keys
     pub data: Vec<ApiKey>,
@@ -130,6 +155,10 @@ pub struct ApiKeyListRequest {
     pub status: Option<ApiKeyStatus>,
 
     /// Filter by Workspace ID
+    ///
+    /// Keys in the default Workspace have no Workspace ID, so they cannot be selected with this
+    /// filter; list without it and keep the keys for which [`ApiKey::in_default_workspace`]
+    /// returns `true`.
     #[builder(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub workspace_id: Option<String>,
@@ -139,12 +168,19 @@ pub struct ApiKeyListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_by_user_id: Option<String>,
 
+    /// Additional query parameters sent as-is, for filters not yet modelled by this crate
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub extra_query: Vec<(String, String)>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl_paginated!(ApiKeyList, ApiKey, ApiKeyListRequest, ApiKeyListBuilder);
+
 /// Request parameters for getting a specific API key.
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -161,6 +197,27 @@ pub struct ApiKeyRequest {
     pub credentials: Option<Credentials>,
 }
 
+/// Request parameters for creating an API key.
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(derive(Clone, Debug, PartialEq))]
+#[builder(pattern = "owned")]
+#[builder(name = "ApiKeyCreateBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct ApiKeyCreateRequest {
+    /// Name of the API key
+    pub name: String,
+
+    /// ID of the Workspace to create the API key in (defaults to the default Workspace)
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
+
+    /// Credentials for authentication (not serialized)
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub credentials: Option<Credentials>,
+}
+
 /// Request parameters for updating an API key.
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -188,6 +245,22 @@ pub struct ApiKeyUpdateRequest {
     pub credentials: Option<Credentials>,
 }
 
+/// Request parameters for archiving an API key.
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(derive(Clone, Debug, PartialEq))]
+#[builder(pattern = "owned")]
+#[builder(name = "ApiKeyArchiveBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct ApiKeyArchiveRequest {
+    /// API key identifier
+    pub api_key_id: String,
+
+    /// Credentials for authentication (not serialized)
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub credentials: Option<Credentials>,
+}
+
 impl ApiKeyList {
     /// Creates a builder for listing API keys.
     ///
@@ -227,6 +300,7 @@ impl ApiKeyList {
     ///     status: None,
     ///     workspace_id: None,
     ///     created_by_user_id: None,
+    ///     extra_query: Vec::new(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -235,6 +309,7 @@ impl ApiKeyList {
     /// # }
     /// ```
     pub async fn create(request: ApiKeyListRequest) -> ApiResponseOrError<Self> {
+        validate_limit(request.limit)?;
         let credentials_opt = request.credentials.clone();
 
         // Build query parameters
@@ -249,7 +324,7 @@ impl ApiKeyList {
             query_params.push(("limit", limit.to_string()));
         }
         if let Some(status) = &request.status {
-            query_params.push(("status", format!("{:?}", status).to_lowercase()));
+            query_params.push(("status", status.as_str().to_string()));
         }
         if let Some(workspace_id) = &request.workspace_id {
             query_params.push(("workspace_id", workspace_id.clone()));
@@ -261,7 +336,7 @@ impl ApiKeyList {
         anthropic_request_json(
             Method::GET,
             "organizations/api_keys",
-            |r| r.query(&query_params),
+            |r| r.query(&query_params).query(&request.extra_query),
             credentials_opt,
         )
         .await
@@ -269,6 +344,42 @@ impl ApiKeyList {
 }
 
 impl ApiKey {
+    /// Value of the object `type` field for API keys.
+    pub const TYPE: &'static str = "api_key";
+
+    /// Parses `created_at` into a [`SystemTime`], or `None` if the timestamp is malformed.
+    pub fn created_at_time(&self) -> Option<SystemTime> {
+        to_system_time(&self.created_at)
+    }
+
+    /// Returns `true` if the key belongs to the default Workspace rather than a named one.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut api_keys = ApiKeyList::builder()
+    ///     .credentials(credentials)
+    ///     .status(ApiKeyStatus::Active)
+    ///     .into_stream();
+    ///
+    /// while let Some(api_key) = api_keys.recv().await {
+    ///     let api_key = api_key?;
+    ///     if api_key.in_default_workspace() {
+    ///         println!("{} ({})", api_key.name, api_key.id);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn in_default_workspace(&self) -> bool {
+        self.workspace_id.is_none()
+    }
+
     /// Creates a builder for getting a specific API key.
     ///
     /// # Example
@@ -315,6 +426,64 @@ impl ApiKey {
         anthropic_request_json(Method::GET, &route, |r| r, credentials_opt).await
     }
 
+    /// Creates a builder for creating a new API key.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let new_api_key = ApiKey::create_builder("Provisioning Key")
+    ///     .credentials(credentials)
+    ///     .workspace_id("wrkspc_123456789")
+    ///     .create()
+    ///     .await?;
+    ///
+    /// // The full secret is only available in the create response.
+    /// println!("New key: {}", new_api_key.key.unwrap_or_default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_builder(name: impl Into<String>) -> ApiKeyCreateBuilder {
+        ApiKeyCreateBuilder::create_empty().name(name)
+    }
+
+    /// Creates a new API key with the given request parameters.
+    ///
+    /// The returned [`ApiKey`] includes the full secret in its `key` field.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    /// let request = ApiKeyCreateRequest {
+    ///     name: "Provisioning Key".to_string(),
+    ///     workspace_id: Some("wrkspc_123456789".to_string()),
+    ///     credentials: Some(credentials),
+    /// };
+    ///
+    /// let new_api_key = ApiKey::create_new(request).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_new(request: ApiKeyCreateRequest) -> ApiResponseOrError<Self> {
+        let credentials_opt = request.credentials.clone();
+
+        anthropic_request_json(
+            Method::POST,
+            "organizations/api_keys",
+            |r| r.json(&request),
+            credentials_opt,
+        )
+        .await
+    }
+
     /// Creates a builder for updating an API key.
     ///
     /// # Example
@@ -364,6 +533,58 @@ impl ApiKey {
 
         anthropic_request_json(Method::POST, &route, |r| r.json(&request), credentials_opt).await
     }
+
+    /// Creates a builder for archiving an API key.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let archived_api_key = ApiKey::archive_builder("api_key_123456789")
+    ///     .credentials(credentials)
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn archive_builder(api_key_id: impl Into<String>) -> ApiKeyArchiveBuilder {
+        ApiKeyArchiveBuilder::create_empty().api_key_id(api_key_id)
+    }
+
+    /// Archives an API key with the given request parameters.
+    ///
+    /// This is equivalent to an update that sets the status to [`ApiKeyStatus::Archived`].
+    /// Archived keys cannot be used or reactivated.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    /// let request = ApiKeyArchiveRequest {
+    ///     api_key_id: "api_key_123456789".to_string(),
+    ///     credentials: Some(credentials),
+    /// };
+    ///
+    /// let archived_api_key = ApiKey::archive(request).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn archive(request: ApiKeyArchiveRequest) -> ApiResponseOrError<Self> {
+        Self::update(ApiKeyUpdateRequest {
+            api_key_id: request.api_key_id,
+            name: None,
+            status: Some(ApiKeyStatus::Archived),
+            credentials: request.credentials,
+        })
+        .await
+    }
 }
 
 // Builder convenience methods
@@ -390,9 +611,41 @@ impl ApiKeyListBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<ApiKeyList> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         ApiKeyList::create(request).await
     }
+
+    /// Lists all API keys, transparently fetching subsequent pages until there are no more.
+    ///
+    /// Every page is requested with the same credentials and limit. Items are delivered on
+    /// the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut api_keys = ApiKeyList::builder()
+    ///     .credentials(credentials)
+    ///     .limit(100u32)
+    ///     .into_stream();
+    ///
+    /// while let Some(api_key) = api_keys.recv().await {
+    ///     println!("{:?}", api_key?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<ApiKey>> {
+        match self.build() {
+            Ok(request) => ApiKeyList::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
 }
 
 impl ApiKeyBuilder {
@@ -417,11 +670,38 @@ impl ApiKeyBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<ApiKey> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         ApiKey::create(request).await
     }
 }
 
+impl ApiKeyCreateBuilder {
+    /// Creates a new API key create request and returns the response.
+    ///
+    /// This is a convenience method that builds the request from the builder
+    /// and sends it to the API Keys API.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let new_api_key = ApiKey::create_builder("Provisioning Key")
+    ///     .credentials(credentials)
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(self) -> ApiResponseOrError<ApiKey> {
+        let request = self.build().map_err(builder_error)?;
+        ApiKey::create_new(request).await
+    }
+}
+
 impl ApiKeyUpdateBuilder {
     /// Creates a new API key update request and returns the response.
     ///
@@ -446,16 +726,141 @@ impl ApiKeyUpdateBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<ApiKey> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         ApiKey::update(request).await
     }
 }
 
+impl ApiKeyArchiveBuilder {
+    /// Creates a new API key archive request and returns the response.
+    ///
+    /// This is a convenience method that builds the request from the builder
+    /// and sends it to the API Keys API.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::api_keys::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let archived_api_key = ApiKey::archive_builder("api_key_123456789")
+    ///     .credentials(credentials)
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(self) -> ApiResponseOrError<ApiKey> {
+        let request = self.build().map_err(builder_error)?;
+        ApiKey::archive(request).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::{json_response, MockServer};
     use crate::Credentials;
 
+    #[tokio::test]
+    async fn test_create_api_key_returns_secret() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{
+                "id": "apikey_01",
+                "name": "Provisioning Key",
+                "created_at": "2025-01-01T00:00:00Z",
+                "created_by": {"id": "user_01", "type": "user"},
+                "partial_key_hint": "sk-ant-api03-abc...xyz",
+                "status": "active",
+                "type": "api_key",
+                "workspace_id": "wrkspc_01",
+                "key": "sk-ant-api03-secret"
+            }"#,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let api_key = ApiKey::create_builder("Provisioning Key")
+            .credentials(credentials)
+            .workspace_id("wrkspc_01")
+            .create()
+            .await
+            .unwrap();
+
+        assert_eq!(api_key.key.as_deref(), Some("sk-ant-api03-secret"));
+        assert_eq!(api_key.workspace_id.as_deref(), Some("wrkspc_01"));
+        assert!(!api_key.in_default_workspace());
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("POST /v1/organizations/api_keys "));
+        assert!(request.ends_with(r#"{"name":"Provisioning Key","workspace_id":"wrkspc_01"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_archive_api_key() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{
+                "id": "apikey_01",
+                "name": "Provisioning Key",
+                "created_at": "2025-01-01T00:00:00Z",
+                "created_by": {"id": "user_01", "type": "user"},
+                "partial_key_hint": "sk-ant-api03-abc...xyz",
+                "status": "archived",
+                "type": "api_key",
+                "workspace_id": null
+            }"#,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let api_key = ApiKey::archive_builder("apikey_01")
+            .credentials(credentials)
+            .create()
+            .await
+            .unwrap();
+
+        assert_eq!(api_key.status, ApiKeyStatus::Archived);
+        assert!(api_key.in_default_workspace());
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("POST /v1/organizations/api_keys/apikey_01 "));
+        assert!(request.ends_with(r#"{"status":"archived"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_list_api_keys_status_filter() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{"data": [], "first_id": null, "last_id": null, "has_more": false}"#,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        ApiKeyList::builder()
+            .credentials(credentials)
+            .status(ApiKeyStatus::Inactive)
+            .query_param("name_contains", "ci key")
+            .create()
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert!(request
+            .starts_with("GET /v1/organizations/api_keys?status=inactive&name_contains=ci+key "));
+
+        for status in [
+            ApiKeyStatus::Active,
+            ApiKeyStatus::Inactive,
+            ApiKeyStatus::Archived,
+        ] {
+            assert_eq!(serde_json::to_value(&status).unwrap(), status.as_str());
+        }
+    }
+
     #[tokio::test]
     #[ignore] // Requires admin API key
     async fn test_list_api_keys() {
@@ -467,7 +872,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(api_keys.data.len() > 0);
+        assert!(!api_keys.data.is_empty());
     }
 
     #[tokio::test]
@@ -41,10 +41,17 @@
 //! }
 //! ```
 
-use crate::{anthropic_request_json, ApiResponseOrError, Credentials};
+use crate::pagination::{impl_paginated, validate_limit};
+use crate::timestamp::{to_system_time, Timestamp};
+use crate::{anthropic_request_json, builder_error, ApiResponseOrError, Credentials};
+#[cfg(feature = "runtime")]
+use crate::{error_channel, pagination::Paginated};
 use derive_builder::Builder;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::Receiver;
 
 /// Organization role of an invited user
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -75,16 +82,16 @@ pub enum InviteStatus {
 }
 
 /// An invite to the organization
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Invite {
     /// Unique invite identifier
     pub id: String,
     /// Email of the user being invited
     pub email: String,
     /// RFC 3339 datetime string indicating when the invite was created
-    pub invited_at: String,
+    pub invited_at: Timestamp,
     /// RFC 3339 datetime string indicating when the invite expires
-    pub expires_at: String,
+    pub expires_at: Timestamp,
     /// Role assigned to the invited user
     pub role: InviteRole,
     /// Current status of the invite
@@ -95,7 +102,7 @@ pub struct Invite {
 }
 
 /// Response from the List Invites API
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct InviteList {
     /// List of invites in the organization
     pub data: Vec<Invite>,
@@ -108,7 +115,7 @@ pub struct InviteList {
 }
 
 /// Response from the Delete Invite API
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct InviteDeleted {
     /// ID of the deleted invite
     pub id: String,
@@ -117,6 +124,11 @@ pub struct InviteDeleted {
     pub deleted_type: String,
 }
 
+impl InviteDeleted {
+    /// Value of the object `type` field for deleted invites.
+    pub const TYPE: &'static str = "invite_deleted";
+}
+
 /// Request parameters for listing invites
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -139,12 +151,19 @@ pub struct InviteListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<u32>,
 
+    /// Additional query parameters sent as-is, for filters not yet modelled by this crate
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub extra_query: Vec<(String, String)>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl_paginated!(InviteList, Invite, InviteListRequest, InviteListBuilder);
+
 /// Request parameters for getting a specific invite
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -232,6 +251,7 @@ impl InviteList {
     ///     before_id: None,
     ///     after_id: None,
     ///     limit: Some(20),
+    ///     extra_query: Vec::new(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -240,6 +260,7 @@ impl InviteList {
     /// # }
     /// ```
     pub async fn create(request: InviteListRequest) -> ApiResponseOrError<Self> {
+        validate_limit(request.limit)?;
         let credentials_opt = request.credentials.clone();
 
         // Build query parameters
@@ -257,7 +278,7 @@ impl InviteList {
         anthropic_request_json(
             Method::GET,
             "organizations/invites",
-            |r| r.query(&query_params),
+            |r| r.query(&query_params).query(&request.extra_query),
             credentials_opt,
         )
         .await
@@ -265,6 +286,19 @@ impl InviteList {
 }
 
 impl Invite {
+    /// Value of the object `type` field for invites.
+    pub const TYPE: &'static str = "invite";
+
+    /// Parses `invited_at` into a [`SystemTime`], or `None` if the timestamp is malformed.
+    pub fn invited_at_time(&self) -> Option<SystemTime> {
+        to_system_time(&self.invited_at)
+    }
+
+    /// Parses `expires_at` into a [`SystemTime`], or `None` if the timestamp is malformed.
+    pub fn expires_at_time(&self) -> Option<SystemTime> {
+        to_system_time(&self.expires_at)
+    }
+
     /// Creates a builder for getting a specific invite.
     ///
     /// # Example
@@ -436,9 +470,41 @@ impl InviteListBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<InviteList> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         InviteList::create(request).await
     }
+
+    /// Lists all invites, transparently fetching subsequent pages until there are no more.
+    ///
+    /// Every page is requested with the same credentials and limit. Items are delivered on
+    /// the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::invites::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut invites = InviteList::builder()
+    ///     .credentials(credentials)
+    ///     .limit(100u32)
+    ///     .into_stream();
+    ///
+    /// while let Some(invite) = invites.recv().await {
+    ///     println!("{:?}", invite?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<Invite>> {
+        match self.build() {
+            Ok(request) => InviteList::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
 }
 
 impl InviteBuilder {
@@ -463,7 +529,7 @@ impl InviteBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Invite> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Invite::create(request).await
     }
 }
@@ -492,7 +558,7 @@ impl InviteCreateBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Invite> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Invite::create_new(request).await
     }
 }
@@ -519,7 +585,7 @@ impl InviteDeleteBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<InviteDeleted> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Invite::delete(request).await
     }
 }
@@ -540,7 +606,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(invites.data.len() > 0);
+        assert!(!invites.data.is_empty());
     }
 
     #[tokio::test]
@@ -41,10 +41,17 @@
 //! }
 //! ```
 
-use crate::{anthropic_request_json, ApiResponseOrError, Credentials};
+use crate::pagination::{impl_paginated, validate_limit};
+use crate::timestamp::{to_system_time, Timestamp};
+use crate::{anthropic_request_json, builder_error, fetch_many, ApiResponseOrError, Credentials};
+#[cfg(feature = "runtime")]
+use crate::{error_channel, pagination::Paginated};
 use derive_builder::Builder;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::Receiver;
 
 /// Organization role of a user
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -61,7 +68,7 @@ pub enum UserRole {
 }
 
 /// A user in the organization
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct User {
     /// Unique user identifier
     pub id: String,
@@ -70,7 +77,7 @@ pub struct User {
     /// User's name
     pub name: String,
     /// RFC 3339 datetime string indicating when the user joined the organization
-    pub added_at: String,
+    pub added_at: Timestamp,
     /// User's role in the organization
     pub role: UserRole,
     /// Object type (always "user" for Users)
@@ -79,7 +86,7 @@ pub struct User {
 }
 
 /// Response from the List Users API
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct UserList {
     /// List of users in the organization
     pub data: Vec<User>,
@@ -92,7 +99,7 @@ pub struct UserList {
 }
 
 /// Response from the Remove User API
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct UserDeleted {
     /// ID of the deleted user
     pub id: String,
@@ -101,6 +108,11 @@ pub struct UserDeleted {
     pub deleted_type: String,
 }
 
+impl UserDeleted {
+    /// Value of the object `type` field for deleted users.
+    pub const TYPE: &'static str = "user_deleted";
+}
+
 /// Request parameters for listing users
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -128,12 +140,19 @@ pub struct UserListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
 
+    /// Additional query parameters sent as-is, for filters not yet modelled by this crate
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub extra_query: Vec<(String, String)>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl_paginated!(UserList, User, UserListRequest, UserListBuilder);
+
 /// Request parameters for getting a specific user
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -223,6 +242,7 @@ impl UserList {
     ///     after_id: None,
     ///     limit: Some(20),
     ///     email: None,
+    ///     extra_query: Vec::new(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -231,6 +251,7 @@ impl UserList {
     /// # }
     /// ```
     pub async fn create(request: UserListRequest) -> ApiResponseOrError<Self> {
+        validate_limit(request.limit)?;
         let credentials_opt = request.credentials.clone();
 
         // Build query parameters
@@ -251,7 +272,7 @@ impl UserList {
         anthropic_request_json(
             Method::GET,
             "organizations/users",
-            |r| r.query(&query_params),
+            |r| r.query(&query_params).query(&request.extra_query),
             credentials_opt,
         )
         .await
@@ -259,6 +280,14 @@ impl UserList {
 }
 
 impl User {
+    /// Value of the object `type` field for users.
+    pub const TYPE: &'static str = "user";
+
+    /// Parses `added_at` into a [`SystemTime`], or `None` if the timestamp is malformed.
+    pub fn added_at_time(&self) -> Option<SystemTime> {
+        to_system_time(&self.added_at)
+    }
+
     /// Creates a builder for getting a specific user.
     ///
     /// # Example
@@ -305,6 +334,46 @@ impl User {
         anthropic_request_json(Method::GET, &route, |r| r, credentials_opt).await
     }
 
+    /// Gets several users concurrently.
+    ///
+    /// At most `concurrency` requests are in flight at once. Results are returned in the
+    /// order of `user_ids`, and a failed lookup doesn't stop the others.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::members::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let users = User::get_many(
+    ///     &["user_1", "user_2", "user_3"],
+    ///     8,
+    ///     Some(Credentials::from_env()),
+    /// )
+    /// .await;
+    ///
+    /// for user in users {
+    ///     match user {
+    ///         Ok(user) => println!("{}: {:?}", user.email, user.role),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_many(
+        user_ids: &[impl AsRef<str>],
+        concurrency: usize,
+        credentials: Option<Credentials>,
+    ) -> Vec<ApiResponseOrError<Self>> {
+        fetch_many(user_ids, concurrency, |user_id| {
+            Self::create(UserRequest {
+                user_id: user_id.as_ref().to_string(),
+                credentials: credentials.clone(),
+            })
+        })
+        .await
+    }
+
     /// Creates a builder for updating a user.
     ///
     /// # Example
@@ -424,9 +493,41 @@ impl UserListBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<UserList> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         UserList::create(request).await
     }
+
+    /// Lists all users, transparently fetching subsequent pages until there are no more.
+    ///
+    /// Every page is requested with the same credentials and limit. Items are delivered on
+    /// the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::members::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut users = UserList::builder()
+    ///     .credentials(credentials)
+    ///     .limit(100u32)
+    ///     .into_stream();
+    ///
+    /// while let Some(user) = users.recv().await {
+    ///     println!("{:?}", user?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<User>> {
+        match self.build() {
+            Ok(request) => UserList::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
 }
 
 impl UserBuilder {
@@ -451,7 +552,7 @@ impl UserBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<User> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         User::create(request).await
     }
 }
@@ -479,7 +580,7 @@ impl UserUpdateBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<User> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         User::update(request).await
     }
 }
@@ -506,7 +607,7 @@ impl UserRemoveBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<UserDeleted> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         User::remove(request).await
     }
 }
@@ -514,8 +615,43 @@ impl UserRemoveBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::{json_response, MockServer};
     use crate::Credentials;
 
+    #[tokio::test]
+    async fn test_get_many_users() {
+        let user = |id: &str| {
+            json_response(
+                "200 OK",
+                &format!(
+                    r#"{{"type": "user", "id": "{id}", "email": "{id}@example.com", "name": "User", "added_at": "2024-10-30T23:58:27.427722Z", "role": "user"}}"#
+                ),
+            )
+        };
+        let server = MockServer::start(vec![
+            user("user_1"),
+            json_response(
+                "404 Not Found",
+                r#"{"type": "error", "error": {"type": "not_found_error", "message": "Not found"}}"#,
+            ),
+            user("user_3"),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        // One request at a time, so the canned responses line up with the ids.
+        let users = User::get_many(&["user_1", "user_2", "user_3"], 1, Some(credentials)).await;
+
+        assert_eq!(users.len(), 3);
+        assert_eq!(users[0].as_ref().unwrap().id, "user_1");
+        assert_eq!(
+            users[1].as_ref().unwrap_err().error.error_type,
+            "not_found_error"
+        );
+        assert_eq!(users[2].as_ref().unwrap().id, "user_3");
+        assert!(server.requests()[1].starts_with("GET /v1/organizations/users/user_2 "));
+    }
+
     #[tokio::test]
     #[ignore] // Requires admin API key
     async fn test_list_users() {
@@ -527,7 +663,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(users.data.len() > 0);
+        assert!(!users.data.is_empty());
     }
 
     #[tokio::test]
@@ -1,4 +1,5 @@
 pub mod api_keys;
 pub mod invites;
 pub mod members;
+pub mod usage;
 pub mod workspace;
@@ -0,0 +1,784 @@
+//! # Usage and Cost Admin API
+//!
+//! This module provides a Rust interface to Anthropic's Admin API for usage and cost reporting, which allows you to
+//! retrieve [token usage](https://docs.anthropic.com/en/api/admin-api/usage-cost/get-messages-usage-report) and
+//! [cost](https://docs.anthropic.com/en/api/admin-api/usage-cost/get-cost-report) for your organization.
+//!
+//! ## Key Features
+//!
+//! - Get Messages API token usage in time buckets, grouped by workspace, model, API key, and more
+//! - Get costs in USD in daily buckets, grouped by workspace or description
+//! - Transparent pagination across all buckets in a time range
+//!
+//! ## Basic Usage
+//!
+//! ```no_run
+//! use anthropic_api::{admin::usage::*, Credentials};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let credentials = Credentials::from_env();
+//!
+//!     // Daily token usage per model
+//!     let usage = UsageReport::builder("2025-01-01T00:00:00Z")
+//!         .credentials(credentials.clone())
+//!         .ending_at("2025-01-08T00:00:00Z")
+//!         .bucket_width(BucketWidth::OneDay)
+//!         .group_by(vec![UsageGroupBy::Model])
+//!         .create()
+//!         .await
+//!         .unwrap();
+//!
+//!     println!("Usage: {:?}", usage.data);
+//!
+//!     // Daily cost per workspace
+//!     let cost = CostReport::builder("2025-01-01T00:00:00Z")
+//!         .credentials(credentials)
+//!         .group_by(vec![CostGroupBy::WorkspaceId])
+//!         .create()
+//!         .await
+//!         .unwrap();
+//!
+//!     println!("Cost: {:?}", cost.data);
+//! }
+//! ```
+
+#[cfg(feature = "runtime")]
+use crate::error_channel;
+use crate::pagination::Paginated;
+use crate::{
+    anthropic_request_json, builder_error, ApiResponseOrError, Credentials, ServerToolUsage,
+};
+use derive_builder::Builder;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::Receiver;
+
+/// Width of each time bucket in a report.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BucketWidth {
+    /// One-minute buckets
+    #[serde(rename = "1m")]
+    OneMinute,
+    /// One-hour buckets
+    #[serde(rename = "1h")]
+    OneHour,
+    /// One-day buckets
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl BucketWidth {
+    /// Returns the value used by the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BucketWidth::OneMinute => "1m",
+            BucketWidth::OneHour => "1h",
+            BucketWidth::OneDay => "1d",
+        }
+    }
+}
+
+/// Dimension to group usage results by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroupBy {
+    /// Group by API key
+    ApiKeyId,
+    /// Group by workspace
+    WorkspaceId,
+    /// Group by model
+    Model,
+    /// Group by service tier
+    ServiceTier,
+    /// Group by context window
+    ContextWindow,
+}
+
+impl UsageGroupBy {
+    /// Returns the value used by the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageGroupBy::ApiKeyId => "api_key_id",
+            UsageGroupBy::WorkspaceId => "workspace_id",
+            UsageGroupBy::Model => "model",
+            UsageGroupBy::ServiceTier => "service_tier",
+            UsageGroupBy::ContextWindow => "context_window",
+        }
+    }
+}
+
+/// Dimension to group cost results by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CostGroupBy {
+    /// Group by workspace
+    WorkspaceId,
+    /// Group by cost description (model, token type, etc.)
+    Description,
+}
+
+impl CostGroupBy {
+    /// Returns the value used by the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostGroupBy::WorkspaceId => "workspace_id",
+            CostGroupBy::Description => "description",
+        }
+    }
+}
+
+/// Tokens used to create cache entries, by cache lifetime.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct CacheCreationUsage {
+    /// Input tokens used to create 1 hour cache entries
+    #[serde(default)]
+    pub ephemeral_1h_input_tokens: u64,
+    /// Input tokens used to create 5 minute cache entries
+    #[serde(default)]
+    pub ephemeral_5m_input_tokens: u64,
+}
+
+/// Token usage for one group within a time bucket.
+///
+/// The grouping fields are only set for the dimensions the report was grouped by.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct UsageResult {
+    /// Input tokens that were not read from or written to the cache
+    pub uncached_input_tokens: u64,
+    /// Input tokens used to create cache entries
+    #[serde(default)]
+    pub cache_creation: CacheCreationUsage,
+    /// Input tokens read from the cache
+    pub cache_read_input_tokens: u64,
+    /// Output tokens generated
+    pub output_tokens: u64,
+    /// Server-side tool usage
+    #[serde(default)]
+    pub server_tool_use: Option<ServerToolUsage>,
+    /// API key the usage is attributed to
+    #[serde(default)]
+    pub api_key_id: Option<String>,
+    /// Workspace the usage is attributed to
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Model that was used
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Service tier that was used
+    #[serde(default)]
+    pub service_tier: Option<String>,
+    /// Context window that was used
+    #[serde(default)]
+    pub context_window: Option<String>,
+}
+
+/// Token usage within a single time bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct UsageBucket {
+    /// RFC 3339 datetime string for the start of the bucket (inclusive)
+    pub starting_at: String,
+    /// RFC 3339 datetime string for the end of the bucket (exclusive)
+    pub ending_at: String,
+    /// Usage for each group in the bucket
+    pub results: Vec<UsageResult>,
+}
+
+/// Response from the Messages Usage Report API.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct UsageReport {
+    /// Usage buckets in the requested time range
+    pub data: Vec<UsageBucket>,
+    /// Indicates if there are more results
+    pub has_more: bool,
+    /// Token to pass as `page` to fetch the next page of results
+    pub next_page: Option<String>,
+}
+
+/// Cost for one group within a time bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CostResult {
+    /// Currency of the amount (always "USD")
+    pub currency: String,
+    /// Cost as a decimal string, in the lowest currency units (cents for USD)
+    pub amount: String,
+    /// Workspace the cost is attributed to
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Description of the cost item
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Type of cost (e.g. "tokens", "web_search")
+    #[serde(default)]
+    pub cost_type: Option<String>,
+    /// Context window that was used
+    #[serde(default)]
+    pub context_window: Option<String>,
+    /// Model that was used
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Service tier that was used
+    #[serde(default)]
+    pub service_tier: Option<String>,
+    /// Type of token billed (e.g. "uncached_input_tokens", "output_tokens")
+    #[serde(default)]
+    pub token_type: Option<String>,
+}
+
+/// Cost within a single time bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CostBucket {
+    /// RFC 3339 datetime string for the start of the bucket (inclusive)
+    pub starting_at: String,
+    /// RFC 3339 datetime string for the end of the bucket (exclusive)
+    pub ending_at: String,
+    /// Cost for each group in the bucket
+    pub results: Vec<CostResult>,
+}
+
+/// Response from the Cost Report API.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CostReport {
+    /// Cost buckets in the requested time range
+    pub data: Vec<CostBucket>,
+    /// Indicates if there are more results
+    pub has_more: bool,
+    /// Token to pass as `page` to fetch the next page of results
+    pub next_page: Option<String>,
+}
+
+/// Request parameters for the Messages Usage Report.
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(derive(Clone, Debug, PartialEq))]
+#[builder(pattern = "owned")]
+#[builder(name = "UsageReportBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct UsageReportRequest {
+    /// RFC 3339 datetime string; buckets starting at or after this time are returned
+    pub starting_at: String,
+
+    /// RFC 3339 datetime string; buckets starting before this time are returned
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ending_at: Option<String>,
+
+    /// Width of each time bucket
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket_width: Option<BucketWidth>,
+
+    /// Dimensions to group results by
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<Vec<UsageGroupBy>>,
+
+    /// Restrict usage to these models
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub models: Option<Vec<String>>,
+
+    /// Restrict usage to these API keys
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_ids: Option<Vec<String>>,
+
+    /// Restrict usage to these workspaces
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_ids: Option<Vec<String>>,
+
+    /// Maximum number of buckets to return per page
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    /// Page token from a previous response's `next_page`
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+
+    /// Credentials for authentication (not serialized)
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub credentials: Option<Credentials>,
+}
+
+/// Request parameters for the Cost Report.
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(derive(Clone, Debug, PartialEq))]
+#[builder(pattern = "owned")]
+#[builder(name = "CostReportBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct CostReportRequest {
+    /// RFC 3339 datetime string; buckets starting at or after this time are returned
+    pub starting_at: String,
+
+    /// RFC 3339 datetime string; buckets starting before this time are returned
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ending_at: Option<String>,
+
+    /// Width of each time bucket (the cost report only supports daily buckets)
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket_width: Option<BucketWidth>,
+
+    /// Dimensions to group results by
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<Vec<CostGroupBy>>,
+
+    /// Maximum number of buckets to return per page
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    /// Page token from a previous response's `next_page`
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+
+    /// Credentials for authentication (not serialized)
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub credentials: Option<Credentials>,
+}
+
+impl UsageReport {
+    /// Creates a builder for the Messages Usage Report starting at the given RFC 3339 time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let usage = UsageReport::builder("2025-01-01T00:00:00Z")
+    ///     .credentials(credentials)
+    ///     .bucket_width(BucketWidth::OneHour)
+    ///     .group_by(vec![UsageGroupBy::WorkspaceId, UsageGroupBy::Model])
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(starting_at: impl Into<String>) -> UsageReportBuilder {
+        UsageReportBuilder::create_empty().starting_at(starting_at)
+    }
+
+    /// Gets a page of the Messages Usage Report with the given request parameters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    /// let request = UsageReportRequest {
+    ///     starting_at: "2025-01-01T00:00:00Z".to_string(),
+    ///     ending_at: Some("2025-01-08T00:00:00Z".to_string()),
+    ///     bucket_width: Some(BucketWidth::OneDay),
+    ///     group_by: Some(vec![UsageGroupBy::Model]),
+    ///     models: None,
+    ///     api_key_ids: None,
+    ///     workspace_ids: None,
+    ///     limit: None,
+    ///     page: None,
+    ///     credentials: Some(credentials),
+    /// };
+    ///
+    /// let usage = UsageReport::create(request).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(request: UsageReportRequest) -> ApiResponseOrError<Self> {
+        let credentials_opt = request.credentials.clone();
+
+        // Build query parameters
+        let mut query_params = vec![("starting_at", request.starting_at.clone())];
+        if let Some(ending_at) = &request.ending_at {
+            query_params.push(("ending_at", ending_at.clone()));
+        }
+        if let Some(bucket_width) = request.bucket_width {
+            query_params.push(("bucket_width", bucket_width.as_str().to_string()));
+        }
+        for group_by in request.group_by.iter().flatten() {
+            query_params.push(("group_by[]", group_by.as_str().to_string()));
+        }
+        for model in request.models.iter().flatten() {
+            query_params.push(("models[]", model.clone()));
+        }
+        for api_key_id in request.api_key_ids.iter().flatten() {
+            query_params.push(("api_key_ids[]", api_key_id.clone()));
+        }
+        for workspace_id in request.workspace_ids.iter().flatten() {
+            query_params.push(("workspace_ids[]", workspace_id.clone()));
+        }
+        if let Some(limit) = request.limit {
+            query_params.push(("limit", limit.to_string()));
+        }
+        if let Some(page) = &request.page {
+            query_params.push(("page", page.clone()));
+        }
+
+        anthropic_request_json(
+            Method::GET,
+            "organizations/usage_report/messages",
+            |r| r.query(&query_params),
+            credentials_opt,
+        )
+        .await
+    }
+}
+
+impl CostReport {
+    /// Creates a builder for the Cost Report starting at the given RFC 3339 time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let cost = CostReport::builder("2025-01-01T00:00:00Z")
+    ///     .credentials(credentials)
+    ///     .group_by(vec![CostGroupBy::WorkspaceId])
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(starting_at: impl Into<String>) -> CostReportBuilder {
+        CostReportBuilder::create_empty().starting_at(starting_at)
+    }
+
+    /// Gets a page of the Cost Report with the given request parameters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    /// let request = CostReportRequest {
+    ///     starting_at: "2025-01-01T00:00:00Z".to_string(),
+    ///     ending_at: None,
+    ///     bucket_width: Some(BucketWidth::OneDay),
+    ///     group_by: Some(vec![CostGroupBy::Description]),
+    ///     limit: None,
+    ///     page: None,
+    ///     credentials: Some(credentials),
+    /// };
+    ///
+    /// let cost = CostReport::create(request).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(request: CostReportRequest) -> ApiResponseOrError<Self> {
+        let credentials_opt = request.credentials.clone();
+
+        // Build query parameters
+        let mut query_params = vec![("starting_at", request.starting_at.clone())];
+        if let Some(ending_at) = &request.ending_at {
+            query_params.push(("ending_at", ending_at.clone()));
+        }
+        if let Some(bucket_width) = request.bucket_width {
+            query_params.push(("bucket_width", bucket_width.as_str().to_string()));
+        }
+        for group_by in request.group_by.iter().flatten() {
+            query_params.push(("group_by[]", group_by.as_str().to_string()));
+        }
+        if let Some(limit) = request.limit {
+            query_params.push(("limit", limit.to_string()));
+        }
+        if let Some(page) = &request.page {
+            query_params.push(("page", page.clone()));
+        }
+
+        anthropic_request_json(
+            Method::GET,
+            "organizations/cost_report",
+            |r| r.query(&query_params),
+            credentials_opt,
+        )
+        .await
+    }
+}
+
+// Reports are paginated with a `next_page` token rather than object IDs.
+impl Paginated for UsageReport {
+    type Item = UsageBucket;
+    type Request = UsageReportRequest;
+
+    fn fetch_page(request: Self::Request) -> impl Future<Output = ApiResponseOrError<Self>> + Send {
+        UsageReport::create(request)
+    }
+
+    fn next_page_request(&self, mut request: Self::Request) -> Option<Self::Request> {
+        if !self.has_more {
+            return None;
+        }
+        request.page = Some(self.next_page.clone()?);
+        Some(request)
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+impl Paginated for CostReport {
+    type Item = CostBucket;
+    type Request = CostReportRequest;
+
+    fn fetch_page(request: Self::Request) -> impl Future<Output = ApiResponseOrError<Self>> + Send {
+        CostReport::create(request)
+    }
+
+    fn next_page_request(&self, mut request: Self::Request) -> Option<Self::Request> {
+        if !self.has_more {
+            return None;
+        }
+        request.page = Some(self.next_page.clone()?);
+        Some(request)
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+// Builder convenience methods
+impl UsageReportBuilder {
+    /// Creates a new usage report request and returns the response.
+    ///
+    /// This is a convenience method that builds the request from the builder
+    /// and sends it to the Usage Report API.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let usage = UsageReport::builder("2025-01-01T00:00:00Z")
+    ///     .credentials(credentials)
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(self) -> ApiResponseOrError<UsageReport> {
+        let request = self.build().map_err(builder_error)?;
+        UsageReport::create(request).await
+    }
+
+    /// Lists all usage buckets in the time range, transparently fetching subsequent pages.
+    ///
+    /// Every page is requested with the same credentials and parameters. Buckets are delivered
+    /// on the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut buckets = UsageReport::builder("2025-01-01T00:00:00Z")
+    ///     .credentials(credentials)
+    ///     .into_stream();
+    ///
+    /// while let Some(bucket) = buckets.recv().await {
+    ///     println!("{:?}", bucket?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<UsageBucket>> {
+        match self.build() {
+            Ok(request) => UsageReport::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
+}
+
+impl CostReportBuilder {
+    /// Creates a new cost report request and returns the response.
+    ///
+    /// This is a convenience method that builds the request from the builder
+    /// and sends it to the Cost Report API.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let cost = CostReport::builder("2025-01-01T00:00:00Z")
+    ///     .credentials(credentials)
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(self) -> ApiResponseOrError<CostReport> {
+        let request = self.build().map_err(builder_error)?;
+        CostReport::create(request).await
+    }
+
+    /// Lists all cost buckets in the time range, transparently fetching subsequent pages.
+    ///
+    /// Every page is requested with the same credentials and parameters. Buckets are delivered
+    /// on the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::usage::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut buckets = CostReport::builder("2025-01-01T00:00:00Z")
+    ///     .credentials(credentials)
+    ///     .into_stream();
+    ///
+    /// while let Some(bucket) = buckets.recv().await {
+    ///     println!("{:?}", bucket?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<CostBucket>> {
+        match self.build() {
+            Ok(request) => CostReport::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{json_response, MockServer};
+    use crate::Credentials;
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_usage_report_pages() {
+        let server = MockServer::start(vec![
+            json_response(
+                "200 OK",
+                r#"{
+                    "data": [{
+                        "starting_at": "2025-01-01T00:00:00Z",
+                        "ending_at": "2025-01-02T00:00:00Z",
+                        "results": [{
+                            "uncached_input_tokens": 1500,
+                            "cache_creation": {"ephemeral_1h_input_tokens": 0, "ephemeral_5m_input_tokens": 200},
+                            "cache_read_input_tokens": 300,
+                            "output_tokens": 500,
+                            "server_tool_use": {"web_search_requests": 2},
+                            "api_key_id": null,
+                            "workspace_id": "wrkspc_01",
+                            "model": "claude-3-7-sonnet-20250219",
+                            "service_tier": null,
+                            "context_window": null
+                        }]
+                    }],
+                    "has_more": true,
+                    "next_page": "page_2"
+                }"#,
+            ),
+            json_response(
+                "200 OK",
+                r#"{
+                    "data": [{"starting_at": "2025-01-02T00:00:00Z", "ending_at": "2025-01-03T00:00:00Z", "results": []}],
+                    "has_more": false,
+                    "next_page": null
+                }"#,
+            ),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let mut buckets = UsageReport::builder("2025-01-01T00:00:00Z")
+            .credentials(credentials)
+            .bucket_width(BucketWidth::OneDay)
+            .group_by(vec![UsageGroupBy::WorkspaceId, UsageGroupBy::Model])
+            .into_stream();
+
+        let first = buckets.recv().await.unwrap().unwrap();
+        let result = &first.results[0];
+        assert_eq!(result.uncached_input_tokens, 1500);
+        assert_eq!(result.cache_creation.ephemeral_5m_input_tokens, 200);
+        assert_eq!(result.workspace_id.as_deref(), Some("wrkspc_01"));
+        assert_eq!(result.model.as_deref(), Some("claude-3-7-sonnet-20250219"));
+        let second = buckets.recv().await.unwrap().unwrap();
+        assert!(second.results.is_empty());
+        assert!(buckets.recv().await.is_none());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /v1/organizations/usage_report/messages?starting_at=2025-01-01T00%3A00%3A00Z&bucket_width=1d&group_by%5B%5D=workspace_id&group_by%5B%5D=model "
+        ));
+        assert!(requests[1].contains("&page=page_2 "));
+    }
+
+    #[tokio::test]
+    async fn test_cost_report() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{
+                "data": [{
+                    "starting_at": "2025-01-01T00:00:00Z",
+                    "ending_at": "2025-01-02T00:00:00Z",
+                    "results": [{
+                        "currency": "USD",
+                        "amount": "123.78912",
+                        "workspace_id": "wrkspc_01",
+                        "description": null,
+                        "cost_type": null,
+                        "context_window": null,
+                        "model": null,
+                        "service_tier": null,
+                        "token_type": null
+                    }]
+                }],
+                "has_more": false,
+                "next_page": null
+            }"#,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let cost = CostReport::builder("2025-01-01T00:00:00Z")
+            .credentials(credentials)
+            .group_by(vec![CostGroupBy::WorkspaceId])
+            .create()
+            .await
+            .unwrap();
+
+        assert_eq!(cost.data[0].results[0].amount, "123.78912");
+        assert_eq!(cost.data[0].results[0].currency, "USD");
+        assert!(server.requests()[0].starts_with(
+            "GET /v1/organizations/cost_report?starting_at=2025-01-01T00%3A00%3A00Z&group_by%5B%5D=workspace_id "
+        ));
+    }
+}
@@ -56,31 +56,42 @@
 //! }
 //! ```
 
-use crate::{anthropic_request_json, ApiResponseOrError, Credentials};
+use crate::pagination::{impl_paginated, validate_limit};
+use crate::timestamp::{to_system_time, Timestamp};
+use crate::{anthropic_request_json, builder_error, fetch_many, ApiResponseOrError, Credentials};
+#[cfg(feature = "runtime")]
+use crate::{error_channel, pagination::Paginated};
 use derive_builder::Builder;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::Receiver;
 
 /// A workspace available through the Anthropic Admin API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Workspace {
     /// Unique workspace identifier
     pub id: String,
     /// Name of the workspace
     pub name: String,
     /// RFC 3339 datetime string representing the time at which the workspace was created
-    pub created_at: String,
+    pub created_at: Timestamp,
     /// RFC 3339 datetime string indicating when the workspace was archived, or null if the workspace is not archived
-    pub archived_at: Option<String>,
+    pub archived_at: Option<Timestamp>,
     /// Hex color code representing the workspace in the Anthropic Console
     pub display_color: String,
     /// Object type (always "workspace" for Workspaces)
     #[serde(rename = "type")]
     pub workspace_type: String,
+    /// Any other fields the API returns that this struct does not model yet,
+    /// such as workspace-level settings added after this release.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Response from the List Workspaces API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct WorkspaceList {
     /// List of available workspaces
     pub data: Vec<Workspace>,
@@ -119,12 +130,24 @@ pub struct WorkspaceListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<u32>,
 
+    /// Additional query parameters sent as-is, for filters not yet modelled by this crate
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub extra_query: Vec<(String, String)>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl_paginated!(
+    WorkspaceList,
+    Workspace,
+    WorkspaceListRequest,
+    WorkspaceListBuilder
+);
+
 /// Request parameters for getting a specific workspace.
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -171,6 +194,11 @@ pub struct WorkspaceUpdateRequest {
     /// New name for the workspace
     pub name: String,
 
+    /// Additional body fields for workspace settings this crate does not model yet
+    #[serde(flatten)]
+    #[builder(default)]
+    pub extra_body: serde_json::Map<String, serde_json::Value>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
@@ -230,6 +258,7 @@ impl WorkspaceList {
     ///     before_id: None,
     ///     after_id: None,
     ///     limit: Some(20),
+    ///     extra_query: Vec::new(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -238,6 +267,7 @@ impl WorkspaceList {
     /// # }
     /// ```
     pub async fn create(request: WorkspaceListRequest) -> ApiResponseOrError<Self> {
+        validate_limit(request.limit)?;
         let credentials_opt = request.credentials.clone();
 
         // Build query parameters
@@ -258,7 +288,7 @@ impl WorkspaceList {
         anthropic_request_json(
             Method::GET,
             "organizations/workspaces",
-            |r| r.query(&query_params),
+            |r| r.query(&query_params).query(&request.extra_query),
             credentials_opt,
         )
         .await
@@ -266,6 +296,19 @@ impl WorkspaceList {
 }
 
 impl Workspace {
+    /// Value of the object `type` field for workspaces.
+    pub const TYPE: &'static str = "workspace";
+
+    /// Parses `created_at` into a [`SystemTime`], or `None` if the timestamp is malformed.
+    pub fn created_at_time(&self) -> Option<SystemTime> {
+        to_system_time(&self.created_at)
+    }
+
+    /// Parses `archived_at` into a [`SystemTime`], or `None` if the workspace is not archived or the timestamp is malformed.
+    pub fn archived_at_time(&self) -> Option<SystemTime> {
+        self.archived_at.as_ref().and_then(to_system_time)
+    }
+
     /// Creates a builder for getting a specific workspace.
     ///
     /// # Example
@@ -398,6 +441,7 @@ impl Workspace {
     /// let request = WorkspaceUpdateRequest {
     ///     workspace_id: "workspace_123456789".to_string(),
     ///     name: "Updated Workspace Name".to_string(),
+    ///     extra_body: Default::default(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -483,9 +527,41 @@ impl WorkspaceListBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<WorkspaceList> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         WorkspaceList::create(request).await
     }
+
+    /// Lists all workspaces, transparently fetching subsequent pages until there are no more.
+    ///
+    /// Every page is requested with the same credentials and limit. Items are delivered on
+    /// the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::workspace::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut workspaces = WorkspaceList::builder()
+    ///     .credentials(credentials)
+    ///     .limit(100u32)
+    ///     .into_stream();
+    ///
+    /// while let Some(workspace) = workspaces.recv().await {
+    ///     println!("{:?}", workspace?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<Workspace>> {
+        match self.build() {
+            Ok(request) => WorkspaceList::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
 }
 
 impl WorkspaceBuilder {
@@ -510,7 +586,7 @@ impl WorkspaceBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Workspace> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Workspace::create(request).await
     }
 }
@@ -538,12 +614,26 @@ impl WorkspaceCreateBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Workspace> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Workspace::create_new(request).await
     }
 }
 
 impl WorkspaceUpdateBuilder {
+    /// Adds a body field that has no dedicated setter, replacing any earlier value for `name`.
+    ///
+    /// Use this for workspace settings the Admin API accepts before this crate models them.
+    pub fn body_param(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.extra_body
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), value.into());
+        self
+    }
+
     /// Creates a new workspace update request and returns the response.
     ///
     /// This is a convenience method that builds the request from the builder
@@ -566,7 +656,7 @@ impl WorkspaceUpdateBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Workspace> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Workspace::update(request).await
     }
 }
@@ -593,13 +683,13 @@ impl WorkspaceArchiveBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Workspace> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Workspace::archive(request).await
     }
 }
 
 /// A workspace member available through the Anthropic Admin API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct WorkspaceMember {
     /// Object type (always "workspace_member" for Workspace Members)
     #[serde(rename = "type")]
@@ -613,7 +703,7 @@ pub struct WorkspaceMember {
 }
 
 /// Response from the List Workspace Members API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct WorkspaceMemberList {
     /// List of workspace members
     pub data: Vec<WorkspaceMember>,
@@ -626,7 +716,7 @@ pub struct WorkspaceMemberList {
 }
 
 /// Response from the Delete Workspace Member API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct WorkspaceMemberDeleted {
     /// Object type (always "workspace_member_deleted" for deleted Workspace Members)
     #[serde(rename = "type")]
@@ -637,6 +727,11 @@ pub struct WorkspaceMemberDeleted {
     pub workspace_id: String,
 }
 
+impl WorkspaceMemberDeleted {
+    /// Value of the object `type` field for deleted workspace members.
+    pub const TYPE: &'static str = "workspace_member_deleted";
+}
+
 /// Role of a workspace member.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -676,12 +771,24 @@ pub struct WorkspaceMemberListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<u32>,
 
+    /// Additional query parameters sent as-is, for filters not yet modelled by this crate
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub extra_query: Vec<(String, String)>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl_paginated!(
+    WorkspaceMemberList,
+    WorkspaceMember,
+    WorkspaceMemberListRequest,
+    WorkspaceMemberListBuilder
+);
+
 /// Request parameters for getting a specific workspace member.
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -804,6 +911,7 @@ impl WorkspaceMemberList {
     ///     before_id: None,
     ///     after_id: None,
     ///     limit: Some(20),
+    ///     extra_query: Vec::new(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -812,6 +920,7 @@ impl WorkspaceMemberList {
     /// # }
     /// ```
     pub async fn create(request: WorkspaceMemberListRequest) -> ApiResponseOrError<Self> {
+        validate_limit(request.limit)?;
         let credentials_opt = request.credentials.clone();
         let route = format!("organizations/workspaces/{}/members", request.workspace_id);
 
@@ -830,7 +939,7 @@ impl WorkspaceMemberList {
         anthropic_request_json(
             Method::GET,
             &route,
-            |r| r.query(&query_params),
+            |r| r.query(&query_params).query(&request.extra_query),
             credentials_opt,
         )
         .await
@@ -838,6 +947,9 @@ impl WorkspaceMemberList {
 }
 
 impl WorkspaceMember {
+    /// Value of the object `type` field for workspace members.
+    pub const TYPE: &'static str = "workspace_member";
+
     /// Creates a builder for getting a specific workspace member.
     ///
     /// # Example
@@ -893,6 +1005,49 @@ impl WorkspaceMember {
         anthropic_request_json(Method::GET, &route, |r| r, credentials_opt).await
     }
 
+    /// Gets several members of a workspace concurrently.
+    ///
+    /// At most `concurrency` requests are in flight at once. Results are returned in the
+    /// order of `user_ids`, and a failed lookup doesn't stop the others.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::workspace::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let members = WorkspaceMember::get_many(
+    ///     "workspace_123456789",
+    ///     &["user_1", "user_2", "user_3"],
+    ///     8,
+    ///     Some(Credentials::from_env()),
+    /// )
+    /// .await;
+    ///
+    /// for member in members {
+    ///     match member {
+    ///         Ok(member) => println!("{}: {:?}", member.user_id, member.workspace_role),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_many(
+        workspace_id: &str,
+        user_ids: &[impl AsRef<str>],
+        concurrency: usize,
+        credentials: Option<Credentials>,
+    ) -> Vec<ApiResponseOrError<Self>> {
+        fetch_many(user_ids, concurrency, |user_id| {
+            Self::create(WorkspaceMemberRequest {
+                workspace_id: workspace_id.to_string(),
+                user_id: user_id.as_ref().to_string(),
+                credentials: credentials.clone(),
+            })
+        })
+        .await
+    }
+
     /// Creates a builder for adding a new workspace member.
     ///
     /// # Example
@@ -1093,9 +1248,41 @@ impl WorkspaceMemberListBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<WorkspaceMemberList> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         WorkspaceMemberList::create(request).await
     }
+
+    /// Lists all workspace members, transparently fetching subsequent pages until there are no more.
+    ///
+    /// Every page is requested with the same credentials and limit. Items are delivered on
+    /// the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{admin::workspace::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut members = WorkspaceMemberList::builder("workspace_id")
+    ///     .credentials(credentials)
+    ///     .limit(100u32)
+    ///     .into_stream();
+    ///
+    /// while let Some(member) = members.recv().await {
+    ///     println!("{:?}", member?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<WorkspaceMember>> {
+        match self.build() {
+            Ok(request) => WorkspaceMemberList::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
 }
 
 impl WorkspaceMemberBuilder {
@@ -1120,7 +1307,7 @@ impl WorkspaceMemberBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<WorkspaceMember> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         WorkspaceMember::create(request).await
     }
 }
@@ -1149,7 +1336,7 @@ impl WorkspaceMemberAddBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<WorkspaceMember> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         WorkspaceMember::add(request).await
     }
 }
@@ -1177,7 +1364,7 @@ impl WorkspaceMemberUpdateBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<WorkspaceMember> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         WorkspaceMember::update(request).await
     }
 }
@@ -1204,7 +1391,7 @@ impl WorkspaceMemberDeleteBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<WorkspaceMemberDeleted> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         WorkspaceMember::delete(request).await
     }
 }
@@ -1212,8 +1399,61 @@ impl WorkspaceMemberDeleteBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::{json_response, MockServer};
     use crate::Credentials;
 
+    #[tokio::test]
+    async fn test_get_many_members() {
+        let member = |user_id: &str| {
+            json_response(
+                "200 OK",
+                &format!(
+                    r#"{{"type": "workspace_member", "user_id": "{user_id}", "workspace_id": "wrkspc_1", "workspace_role": "workspace_user"}}"#
+                ),
+            )
+        };
+        let server = MockServer::start(vec![
+            member("user_1"),
+            json_response(
+                "404 Not Found",
+                r#"{"type": "error", "error": {"type": "not_found_error", "message": "Not found"}}"#,
+            ),
+            member("user_3"),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        // One request at a time, so the canned responses line up with the ids.
+        let members = WorkspaceMember::get_many(
+            "wrkspc_1",
+            &["user_1", "user_2", "user_3"],
+            1,
+            Some(credentials),
+        )
+        .await;
+
+        assert_eq!(members.len(), 3);
+        assert_eq!(members[0].as_ref().unwrap().user_id, "user_1");
+        assert_eq!(
+            members[1].as_ref().unwrap_err().error.error_type,
+            "not_found_error"
+        );
+        assert_eq!(members[2].as_ref().unwrap().user_id, "user_3");
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_into_stream_missing_workspace_id() {
+        let mut members = WorkspaceMemberListBuilder::create_empty()
+            .limit(10u32)
+            .into_stream();
+
+        let error = members.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.error.error_type, "builder");
+        assert!(error.error.message.contains("workspace_id"));
+        assert!(members.recv().await.is_none());
+    }
+
     #[tokio::test]
     #[ignore] // Requires admin API key
     async fn test_list_workspaces() {
@@ -1225,7 +1465,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(workspaces.data.len() > 0);
+        assert!(!workspaces.data.is_empty());
     }
 
     #[tokio::test]
@@ -1254,6 +1494,34 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_workspace_unknown_fields() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r##"{"type": "workspace", "id": "wrkspc_1", "name": "Renamed", "created_at": "2024-10-30T23:58:27.427722Z", "archived_at": null, "display_color": "#6C5BB9", "data_residency": {"workspace_geo": "us"}}"##,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let workspace = Workspace::update_builder("wrkspc_1")
+            .credentials(credentials)
+            .name("Renamed")
+            .body_param("data_residency", serde_json::json!({"workspace_geo": "us"}))
+            .create()
+            .await
+            .unwrap();
+
+        assert_eq!(workspace.name, "Renamed");
+        assert_eq!(
+            workspace.extra["data_residency"],
+            serde_json::json!({"workspace_geo": "us"})
+        );
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("POST /v1/organizations/workspaces/wrkspc_1 "));
+        assert!(request.ends_with(r#"{"name":"Renamed","data_residency":{"workspace_geo":"us"}}"#));
+    }
+
     #[tokio::test]
     #[ignore] // Requires admin API key
     async fn test_list_workspace_members() {
@@ -0,0 +1,616 @@
+//! # Message Batches API
+//!
+//! This module provides a Rust interface to Anthropic's [Message Batches API](https://docs.anthropic.com/en/api/creating-message-batches),
+//! which processes large numbers of Messages requests asynchronously at a reduced cost.
+//!
+//! ## Key Features
+//!
+//! - Create a batch of Messages requests
+//! - Get the status of a batch, or wait for it to end
+//! - Cancel a batch that is still processing
+//! - Stream the results of a finished batch without buffering the whole results file
+//!
+//! ## Basic Usage
+//!
+//! ```no_run
+//! use anthropic_api::{batches::*, messages::*, Credentials};
+//! use futures_util::StreamExt;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let credentials = Credentials::from_env();
+//!
+//!     let params = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+//!         .user_text("Hello, Claude!")
+//!         .build()
+//!         .unwrap();
+//!
+//!     // Create a batch
+//!     let batch = MessageBatch::create_builder(vec![BatchRequest {
+//!         custom_id: "greeting".to_string(),
+//!         params,
+//!     }])
+//!     .credentials(credentials.clone())
+//!     .create()
+//!     .await
+//!     .unwrap();
+//!
+//!     // Later, once the batch has ended, stream its results
+//!     let results = batch.results_stream(Some(credentials)).await.unwrap();
+//!     let mut results = std::pin::pin!(results);
+//!     while let Some(entry) = results.next().await {
+//!         let entry = entry.unwrap();
+//!         println!("{}: {:?}", entry.custom_id, entry.result);
+//!     }
+//! }
+//! ```
+
+#[cfg(feature = "blocking")]
+use crate::block_on;
+use crate::messages::{MessagesRequest, MessagesResponse};
+use crate::timestamp::Timestamp;
+use crate::{
+    anthropic_request, anthropic_request_json, builder_error, error_from_response,
+    resolve_credentials, AnthropicErrorResponse, ApiResponseOrError, Credentials,
+};
+use derive_builder::Builder;
+use futures_util::{Stream, StreamExt};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+
+/// A batch of Messages requests.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct MessageBatch {
+    /// Unique batch identifier
+    pub id: String,
+    /// Object type (always "message_batch" for batches)
+    #[serde(rename = "type")]
+    pub batch_type: String,
+    /// Processing status of the batch
+    pub processing_status: MessageBatchStatus,
+    /// Number of requests in the batch in each state
+    pub request_counts: MessageBatchRequestCounts,
+    /// RFC 3339 datetime string representing the time at which the batch was created
+    pub created_at: Timestamp,
+    /// RFC 3339 datetime string representing the time at which processing ended, if it has
+    pub ended_at: Option<Timestamp>,
+    /// RFC 3339 datetime string representing the time at which the batch expires
+    pub expires_at: Timestamp,
+    /// URL of the results file, available once processing has ended
+    pub results_url: Option<String>,
+    /// RFC 3339 datetime string representing the time at which cancellation was requested, if it was
+    #[serde(default)]
+    pub cancel_initiated_at: Option<Timestamp>,
+}
+
+/// Processing status of a batch.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageBatchStatus {
+    /// Requests in the batch are still being processed
+    InProgress,
+    /// Cancellation was requested and in-flight requests are finishing
+    Canceling,
+    /// Processing has ended and the results are available
+    Ended,
+}
+
+/// Number of requests in a batch in each state.
+///
+/// While a batch is in progress, requests are counted as `processing`; once it has ended
+/// every request is counted under its final outcome.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct MessageBatchRequestCounts {
+    /// Requests still being processed
+    pub processing: u32,
+    /// Requests that completed successfully
+    pub succeeded: u32,
+    /// Requests that failed
+    pub errored: u32,
+    /// Requests that were canceled before being processed
+    pub canceled: u32,
+    /// Requests that expired before being processed
+    pub expired: u32,
+}
+
+/// A single request within a batch.
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchRequest {
+    /// Developer-provided ID used to match results to requests
+    pub custom_id: String,
+    /// Parameters of the Messages request
+    pub params: MessagesRequest,
+}
+
+/// One line of a batch results file.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct MessageBatchResultEntry {
+    /// The `custom_id` of the request this result belongs to
+    pub custom_id: String,
+    /// The outcome of the request
+    pub result: MessageBatchResult,
+}
+
+/// Outcome of a single request within a batch.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageBatchResult {
+    /// The request succeeded
+    Succeeded { message: Box<MessagesResponse> },
+    /// The request failed
+    Errored { error: AnthropicErrorResponse },
+    /// The batch was canceled before the request was processed
+    Canceled,
+    /// The batch expired before the request was processed
+    Expired,
+}
+
+/// Request parameters for creating a batch.
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(derive(Clone, Debug))]
+#[builder(pattern = "owned")]
+#[builder(name = "MessageBatchCreateBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct MessageBatchCreateRequest {
+    /// Requests to process in the batch
+    pub requests: Vec<BatchRequest>,
+
+    /// Credentials for authentication (not serialized)
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub credentials: Option<Credentials>,
+}
+
+/// Request parameters for getting a specific batch.
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(derive(Clone, Debug, PartialEq))]
+#[builder(pattern = "owned")]
+#[builder(name = "MessageBatchBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct MessageBatchRequest {
+    /// ID of the batch
+    pub batch_id: String,
+
+    /// Credentials for authentication (not serialized)
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub credentials: Option<Credentials>,
+}
+
+impl MessageBatch {
+    /// Value of the object `type` field for message batches.
+    pub const TYPE: &'static str = "message_batch";
+
+    /// Creates a builder for getting a specific batch.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{batches::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let batch = MessageBatch::builder("msgbatch_013Zva2CMHLNnXjNJJKqJ2EF")
+    ///     .credentials(credentials)
+    ///     .create()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(batch_id: impl Into<String>) -> MessageBatchBuilder {
+        MessageBatchBuilder::create_empty().batch_id(batch_id)
+    }
+
+    /// Gets a specific batch.
+    pub async fn create(request: MessageBatchRequest) -> ApiResponseOrError<Self> {
+        let credentials_opt = request.credentials.clone();
+        let route = format!("messages/batches/{}", request.batch_id);
+
+        anthropic_request_json(Method::GET, &route, |r| r, credentials_opt).await
+    }
+
+    /// Gets a specific batch, polling every `poll_interval` until its processing has ended.
+    ///
+    /// Requires the `runtime` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{batches::*, Credentials};
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let batch = MessageBatch::builder("msgbatch_013Zva2CMHLNnXjNJJKqJ2EF")
+    ///     .credentials(Credentials::from_env())
+    ///     .retrieve(Duration::from_secs(60))
+    ///     .await?;
+    ///
+    /// println!("{} requests succeeded", batch.request_counts.succeeded);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub async fn retrieve(
+        request: MessageBatchRequest,
+        poll_interval: Duration,
+    ) -> ApiResponseOrError<Self> {
+        loop {
+            let batch = Self::create(request.clone()).await?;
+            if batch.is_ended() {
+                return Ok(batch);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Cancels a batch that is still processing.
+    ///
+    /// The returned batch is usually `canceling`: requests that are already being processed
+    /// finish first, and the remaining requests are reported as canceled once the batch has
+    /// ended. Results for requests that completed before cancellation are still available.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{batches::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let batch = MessageBatch::builder("msgbatch_013Zva2CMHLNnXjNJJKqJ2EF")
+    ///     .credentials(Credentials::from_env())
+    ///     .cancel()
+    ///     .await?;
+    ///
+    /// assert_ne!(batch.processing_status, MessageBatchStatus::InProgress);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn cancel(request: MessageBatchRequest) -> ApiResponseOrError<Self> {
+        let credentials_opt = request.credentials.clone();
+        let route = format!("messages/batches/{}/cancel", request.batch_id);
+
+        anthropic_request_json(Method::POST, &route, |r| r, credentials_opt).await
+    }
+
+    /// Returns whether processing of the batch has ended.
+    pub fn is_ended(&self) -> bool {
+        self.processing_status == MessageBatchStatus::Ended
+    }
+
+    /// Creates a builder for creating a new batch.
+    pub fn create_builder(requests: impl Into<Vec<BatchRequest>>) -> MessageBatchCreateBuilder {
+        MessageBatchCreateBuilder::create_empty().requests(requests)
+    }
+
+    /// Creates a new batch with the given request parameters.
+    pub async fn create_new(request: MessageBatchCreateRequest) -> ApiResponseOrError<Self> {
+        let credentials_opt = request.credentials.clone();
+
+        anthropic_request_json(
+            Method::POST,
+            "messages/batches",
+            |r| r.json(&request),
+            credentials_opt,
+        )
+        .await
+    }
+
+    /// Streams the results of an ended batch.
+    ///
+    /// The results file is parsed line by line as it downloads, so memory use does not grow
+    /// with the size of the batch. An error, such as a malformed line or a dropped connection,
+    /// is yielded as the final item of the stream.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{batches::*, Credentials};
+    /// # use futures_util::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    /// let batch = MessageBatch::builder("msgbatch_013Zva2CMHLNnXjNJJKqJ2EF")
+    ///     .credentials(credentials.clone())
+    ///     .create()
+    ///     .await?;
+    ///
+    /// let results = batch.results_stream(Some(credentials)).await?;
+    /// let mut results = std::pin::pin!(results);
+    /// while let Some(entry) = results.next().await {
+    ///     if let MessageBatchResult::Succeeded { message } = entry?.result {
+    ///         println!("{}", message.text());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn results_stream(
+        &self,
+        credentials: Option<Credentials>,
+    ) -> ApiResponseOrError<impl Stream<Item = ApiResponseOrError<MessageBatchResultEntry>>> {
+        let route = format!("messages/batches/{}/results", self.id);
+        let credentials = resolve_credentials(credentials)?;
+        let response =
+            anthropic_request(Method::GET, &route, |r| r, Some(credentials.clone())).await?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response, &credentials).await);
+        }
+        Ok(jsonl_stream(Box::pin(response.bytes_stream())))
+    }
+}
+
+/// Parses a stream of bytes as JSON Lines, yielding one item per non-empty line.
+fn jsonl_stream<S, B, T>(bytes: S) -> impl Stream<Item = ApiResponseOrError<T>>
+where
+    S: Stream<Item = Result<B, reqwest::Error>> + Unpin,
+    B: AsRef<[u8]>,
+    T: serde::de::DeserializeOwned,
+{
+    futures_util::stream::unfold(Some((bytes, Vec::new())), |state| async move {
+        let (mut bytes, mut buffer) = state?;
+        loop {
+            if let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+                let item = parse_line(&line);
+                let state = item.is_ok().then_some((bytes, buffer));
+                return Some((item, state));
+            }
+            match bytes.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => return Some((Err(e.into()), None)),
+                // The last line may not end with a newline.
+                None if buffer.trim_ascii().is_empty() => return None,
+                None => return Some((parse_line(&buffer), None)),
+            }
+        }
+    })
+}
+
+fn parse_line<T: serde::de::DeserializeOwned>(line: &[u8]) -> ApiResponseOrError<T> {
+    serde_json::from_slice(line).map_err(|e| {
+        AnthropicErrorResponse::new(
+            format!("Failed to parse batch result: {}", e),
+            "json_parse_error".to_string(),
+        )
+    })
+}
+
+// Builder convenience methods
+impl MessageBatchBuilder {
+    /// Gets the batch and returns the response.
+    pub async fn create(self) -> ApiResponseOrError<MessageBatch> {
+        let request = self.build().map_err(builder_error)?;
+        MessageBatch::create(request).await
+    }
+
+    /// Gets the batch, blocking the current thread until it completes.
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(self) -> ApiResponseOrError<MessageBatch> {
+        let request = self.build().map_err(builder_error)?;
+        block_on(MessageBatch::create(request))
+    }
+
+    /// Polls the batch every `poll_interval` until its processing has ended.
+    #[cfg(feature = "runtime")]
+    pub async fn retrieve(self, poll_interval: Duration) -> ApiResponseOrError<MessageBatch> {
+        let request = self.build().map_err(builder_error)?;
+        MessageBatch::retrieve(request, poll_interval).await
+    }
+
+    /// Cancels the batch and returns the response.
+    pub async fn cancel(self) -> ApiResponseOrError<MessageBatch> {
+        let request = self.build().map_err(builder_error)?;
+        MessageBatch::cancel(request).await
+    }
+}
+
+impl MessageBatchCreateBuilder {
+    /// Creates the batch and returns the response.
+    pub async fn create(self) -> ApiResponseOrError<MessageBatch> {
+        let request = self.build().map_err(builder_error)?;
+        MessageBatch::create_new(request).await
+    }
+
+    /// Creates the batch, blocking the current thread until it completes.
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(self) -> ApiResponseOrError<MessageBatch> {
+        let request = self.build().map_err(builder_error)?;
+        block_on(MessageBatch::create_new(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::MessagesBuilder;
+    use crate::tests::{http_response, json_response, MockServer};
+
+    fn batch_json(id: &str) -> String {
+        batch_json_with_status(id, "ended")
+    }
+
+    fn batch_json_with_status(id: &str, status: &str) -> String {
+        format!(
+            r#"{{"id": "{id}", "type": "message_batch", "processing_status": "{status}",
+                "created_at": "2025-02-19T00:00:00Z", "ended_at": "2025-02-19T01:00:00Z",
+                "expires_at": "2025-02-20T00:00:00Z", "results_url": null,
+                "request_counts": {{"processing": 0, "succeeded": 2, "errored": 1, "canceled": 0, "expired": 0}}}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_create_batch() {
+        let server =
+            MockServer::start(vec![json_response("200 OK", &batch_json("msgbatch_1"))]).await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let params = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .user_text("Hi")
+            .build()
+            .unwrap();
+        let batch = MessageBatch::create_builder(vec![BatchRequest {
+            custom_id: "first".to_string(),
+            params,
+        }])
+        .credentials(credentials)
+        .create()
+        .await
+        .unwrap();
+
+        assert_eq!(batch.id, "msgbatch_1");
+        assert_eq!(batch.batch_type, MessageBatch::TYPE);
+        let request = &server.requests()[0];
+        assert!(request.starts_with("POST /v1/messages/batches "));
+        assert!(request.contains(
+            r#"{"requests":[{"custom_id":"first","params":{"model":"claude-3-7-sonnet-20250219""#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_batch() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            &batch_json_with_status("msgbatch_1", "canceling"),
+        )])
+        .await;
+
+        let batch = MessageBatch::builder("msgbatch_1")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .cancel()
+            .await
+            .unwrap();
+        assert_eq!(batch.processing_status, MessageBatchStatus::Canceling);
+        assert!(!batch.is_ended());
+        assert!(server.requests()[0].starts_with("POST /v1/messages/batches/msgbatch_1/cancel "));
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_retrieve_batch() {
+        let server = MockServer::start(vec![
+            json_response(
+                "200 OK",
+                &batch_json_with_status("msgbatch_1", "in_progress"),
+            ),
+            json_response("200 OK", &batch_json("msgbatch_1")),
+        ])
+        .await;
+
+        let batch = MessageBatch::builder("msgbatch_1")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .retrieve(Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert!(batch.is_ended());
+        assert_eq!(
+            batch.request_counts,
+            MessageBatchRequestCounts {
+                processing: 0,
+                succeeded: 2,
+                errored: 1,
+                canceled: 0,
+                expired: 0,
+            }
+        );
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /v1/messages/batches/msgbatch_1 "));
+        assert_eq!(requests.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_results_stream() {
+        let message = r#"{"id": "msg_1", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": [{"type": "text", "text": "Hello"}], "stop_reason": "end_turn", "stop_sequence": null, "type": "message", "usage": {"input_tokens": 1, "output_tokens": 1}}"#;
+        let body = format!(
+            "{{\"custom_id\": \"a\", \"result\": {{\"type\": \"succeeded\", \"message\": {message}}}}}\n\
+             \n\
+             {{\"custom_id\": \"b\", \"result\": {{\"type\": \"errored\", \"error\": {{\"type\": \"error\", \"error\": {{\"type\": \"invalid_request_error\", \"message\": \"Bad\"}}}}}}}}\n\
+             {{\"custom_id\": \"c\", \"result\": {{\"type\": \"expired\"}}}}"
+        );
+        let server = MockServer::start(vec![
+            json_response("200 OK", &batch_json("msgbatch_1")),
+            http_response("200 OK", &[("content-type", "application/binary")], &body),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let batch = MessageBatch::builder("msgbatch_1")
+            .credentials(credentials.clone())
+            .create()
+            .await
+            .unwrap();
+        let entries: Vec<_> = batch
+            .results_stream(Some(credentials))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(entries.len(), 3);
+        let entries: Vec<_> = entries.into_iter().map(Result::unwrap).collect();
+        assert!(matches!(
+            &entries[0].result,
+            MessageBatchResult::Succeeded { message } if message.text() == "Hello"
+        ));
+        assert!(matches!(
+            &entries[1].result,
+            MessageBatchResult::Errored { error } if error.error.error_type == "invalid_request_error"
+        ));
+        assert_eq!(entries[2].custom_id, "c");
+        assert_eq!(entries[2].result, MessageBatchResult::Expired);
+        assert!(server.requests()[1].starts_with("GET /v1/messages/batches/msgbatch_1/results "));
+    }
+
+    #[tokio::test]
+    async fn test_results_stream_error_body_is_redacted() {
+        let server = MockServer::start(vec![http_response(
+            "502 Bad Gateway",
+            &[("content-type", "text/plain")],
+            "upstream rejected x-api-key: test-key",
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let batch: MessageBatch = serde_json::from_str(&batch_json("msgbatch_1")).unwrap();
+
+        let error = match batch.results_stream(Some(credentials)).await {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error,
+        };
+
+        assert_eq!(error.error.error_type, "http");
+        assert_eq!(
+            error.error.message,
+            "Request failed with status 502 Bad Gateway: upstream rejected x-api-key: [REDACTED_API_KEY]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_stream_split_chunks() {
+        let chunks: Vec<Result<&[u8], reqwest::Error>> = vec![
+            Ok(b"{\"custom_id\": \"a\", \"res"),
+            Ok(b"ult\": {\"type\": \"canceled\"}}\n{\"custom_id\""),
+            Ok(b": \"b\", \"result\": {\"type\": \"expired\"}}\nnot json\n"),
+        ];
+        let entries: Vec<ApiResponseOrError<MessageBatchResultEntry>> =
+            jsonl_stream(futures_util::stream::iter(chunks))
+                .collect()
+                .await;
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].as_ref().unwrap().result,
+            MessageBatchResult::Canceled
+        );
+        assert_eq!(entries[1].as_ref().unwrap().custom_id, "b");
+        assert_eq!(
+            entries[2].as_ref().unwrap_err().error.error_type,
+            "json_parse_error"
+        );
+    }
+}
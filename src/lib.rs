@@ -24,36 +24,416 @@
 //! ```
 //!
 //! Then use the library as usual.
+//!
+//! ## WebAssembly
+//!
+//! The default `runtime` feature spawns tasks on the tokio runtime for channel-based streams
+//! and pagination, and sleeps between retries. To build for `wasm32-unknown-unknown`, disable
+//! default features; non-streaming requests keep working, retries are not attempted, and
+//! streaming is available through
+//! [`StreamEvent::create_event_stream`](messages::StreamEvent::create_event_stream).
+//!
+//! ```toml
+//! anthropic-api = { version = "0.0.5", default-features = false, features = ["tracing"] }
+//! ```
+//!
+//! ## Blocking API
+//!
+//! The `blocking` feature adds `create_blocking` methods for callers without an async
+//! runtime, such as synchronous CLIs and build scripts. Each call runs the request on a
+//! private current-thread runtime, so it must not be used from within an async context.
+//! Streaming is not available through the blocking API.
+//!
+//! ```toml
+//! anthropic-api = { version = "0.0.5", features = ["blocking"] }
+//! ```
+//!
+//! ## Cancellation
+//!
+//! Requests run inside the future that sends them, so dropping that future cancels the
+//! request and closes its connection. The `cancellation` feature adds
+//! `create_with_cancellation` methods that take a tokio-util `CancellationToken` instead,
+//! for cancelling from elsewhere in the program.
+//!
+//! ```toml
+//! anthropic-api = { version = "0.0.5", features = ["cancellation"] }
+//! ```
+//!
+//! ## Testing
+//!
+//! Every request goes to the base URL of its [`Credentials`], so tests can point them at a
+//! local mock server instead of the live API. The `test-util` feature provides one in the
+//! `testing` module; any other mock HTTP server works the same way.
+//!
+//! ```no_run
+//! use anthropic_api::Credentials;
+//!
+//! let credentials = Credentials::new("test-key", "http://127.0.0.1:1234/v1/");
+//! ```
+//!
+//! ## Logging
+//!
+//! With the default `tracing` feature, requests are instrumented with spans and log events
+//! through the [`tracing`](https://docs.rs/tracing) crate. Disable it to drop the dependency
+//! and the instrumentation entirely.
 
-use reqwest::{header::CONTENT_TYPE, Client, Method, RequestBuilder, Response};
+#[cfg(not(feature = "tracing"))]
+use no_tracing::{debug, error, info, trace, warn};
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE, USER_AGENT},
+    Client, Method, RequestBuilder, Response, StatusCode,
+};
 use reqwest_eventsource::{CannotCloneRequestError, EventSource, RequestBuilderExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::env;
 use std::env::VarError;
 use std::fmt::Debug;
-use std::sync::{LazyLock, RwLock};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::{channel, Receiver};
+#[cfg(feature = "tracing")]
 use tracing::{debug, error, info, instrument, trace, warn};
 
 pub mod admin;
+pub mod batches;
 pub mod messages;
 pub mod models;
+pub mod pagination;
+#[cfg(feature = "partial-json")]
+pub mod partial_json;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod timestamp;
+
+/// No-op stand-ins for the `tracing` macros used when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+mod no_tracing {
+    macro_rules! noop {
+        ($($arg:tt)*) => {};
+    }
+
+    pub(crate) use noop as debug;
+    pub(crate) use noop as error;
+    pub(crate) use noop as info;
+    pub(crate) use noop as trace;
+    pub(crate) use noop as warn;
+}
 
 /// Default base URL for the Anthropic API.
 pub static DEFAULT_BASE_URL: LazyLock<String> =
     LazyLock::new(|| String::from("https://api.anthropic.com/v1/"));
 
-/// Default credentials loaded from environment variables.
-static DEFAULT_CREDENTIALS: LazyLock<RwLock<Credentials>> =
-    LazyLock::new(|| RwLock::new(Credentials::from_env()));
+/// Default value of the `anthropic-version` header.
+pub const DEFAULT_API_VERSION: &str = "2023-06-01";
+
+/// Default value of the `User-Agent` header.
+pub const DEFAULT_USER_AGENT: &str = concat!("anthropic-rust/", env!("CARGO_PKG_VERSION"));
+
+/// Default credentials loaded from environment variables, if they are set.
+static DEFAULT_CREDENTIALS: LazyLock<RwLock<Option<Credentials>>> =
+    LazyLock::new(|| RwLock::new(Credentials::try_from_env().ok()));
+
+/// Sets the credentials used by requests that don't specify any.
+///
+/// This replaces the credentials loaded from the environment, which is useful when the API
+/// key comes from somewhere else, such as a secrets manager, after startup.
+///
+/// # Examples
+///
+/// ```
+/// use anthropic_api::{default_credentials, set_default_credentials, Credentials};
+///
+/// set_default_credentials(Credentials::new("key-from-secrets-manager", ""));
+/// assert_eq!(default_credentials().unwrap().api_key(), "key-from-secrets-manager");
+/// ```
+pub fn set_default_credentials(credentials: Credentials) {
+    *DEFAULT_CREDENTIALS.write().unwrap() = Some(credentials);
+}
+
+/// Returns the credentials used by requests that don't specify any, if they are configured.
+///
+/// These are loaded from the environment on first use, unless they have been replaced with
+/// [`set_default_credentials`].
+pub fn default_credentials() -> Option<Credentials> {
+    DEFAULT_CREDENTIALS.read().unwrap().clone()
+}
+
+/// Returns the given credentials, falling back to the default credentials.
+///
+/// If no default credentials were loaded at startup, the environment is checked again so the
+/// error reports what is actually missing.
+fn resolve_credentials(
+    credentials_opt: Option<Credentials>,
+) -> Result<Credentials, CredentialsError> {
+    match credentials_opt {
+        Some(credentials) => Ok(credentials),
+        None => match default_credentials() {
+            Some(credentials) => Ok(credentials),
+            None => Credentials::try_from_env(),
+        },
+    }
+}
+
+/// An error loading [`Credentials`] from the environment.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CredentialsError {
+    /// The `ANTHROPIC_API_KEY` environment variable is not set.
+    MissingApiKey,
+    /// An environment variable is set but is not valid unicode.
+    NotUnicode {
+        /// Name of the environment variable.
+        var: &'static str,
+    },
+}
+
+impl std::fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialsError::MissingApiKey => {
+                write!(f, "ANTHROPIC_API_KEY environment variable is required")
+            }
+            CredentialsError::NotUnicode { var } => write!(f, "{var} is not valid unicode"),
+        }
+    }
+}
+
+impl std::error::Error for CredentialsError {}
+
+/// A hook for modifying outgoing requests before they are sent.
+///
+/// Interceptors are attached to [`Credentials`] with [`Credentials::with_interceptor`] and run
+/// after the standard authentication and version headers have been set, so they can add
+/// headers (e.g. a correlation id), override defaults, or otherwise adjust the request.
+///
+/// Any `Fn(RequestBuilder) -> RequestBuilder` closure that is `Send + Sync` implements this trait.
+pub trait RequestInterceptor: Send + Sync {
+    /// Modifies the request before it is sent.
+    fn before(&self, builder: RequestBuilder) -> RequestBuilder;
+}
+
+impl<F> RequestInterceptor for F
+where
+    F: Fn(RequestBuilder) -> RequestBuilder + Send + Sync,
+{
+    fn before(&self, builder: RequestBuilder) -> RequestBuilder {
+        self(builder)
+    }
+}
+
+/// Metadata about a completed response, passed to a [`ResponseObserver`].
+#[derive(Debug, Clone)]
+pub struct ResponseMetadata {
+    /// HTTP method of the request.
+    pub method: Method,
+    /// Full URL of the request.
+    pub url: String,
+    /// HTTP status code of the response.
+    pub status: StatusCode,
+    /// Value of the `request-id` header, if present.
+    pub request_id: Option<String>,
+    /// Rate limit headers (`anthropic-ratelimit-*` and `retry-after`) as name/value pairs.
+    pub rate_limit_headers: Vec<(String, String)>,
+    /// Time from sending the request until the response headers were received.
+    /// Always zero on wasm32.
+    pub latency: Duration,
+}
+
+impl ResponseMetadata {
+    /// Collects the metadata from a response.
+    fn from_response(method: Method, response: &Response, latency: Duration) -> Self {
+        let headers = response.headers();
+        let request_id = headers
+            .get("request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let rate_limit_headers = headers
+            .iter()
+            .filter(|(name, _)| {
+                name.as_str().starts_with("anthropic-ratelimit-") || name.as_str() == "retry-after"
+            })
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        Self {
+            method,
+            url: response.url().to_string(),
+            status: response.status(),
+            request_id,
+            rate_limit_headers,
+            latency,
+        }
+    }
+}
+
+/// A hook for observing every response received from the API.
+///
+/// Observers are attached to [`Credentials`] with [`Credentials::with_observer`] and are called
+/// once the response headers have been received, before the body is read. Streaming requests
+/// are not observed.
+///
+/// Any `Fn(&ResponseMetadata)` closure that is `Send + Sync` implements this trait.
+pub trait ResponseObserver: Send + Sync {
+    /// Called with the metadata of each response.
+    fn after(&self, metadata: &ResponseMetadata);
+}
+
+impl<F> ResponseObserver for F
+where
+    F: Fn(&ResponseMetadata) + Send + Sync,
+{
+    fn after(&self, metadata: &ResponseMetadata) {
+        self(metadata)
+    }
+}
+
+/// Controls automatic retries of failed requests.
+///
+/// Attach it to [`Credentials`] with [`Credentials::with_retry`]. By default, rate limit
+/// (429), overloaded (529), and internal API (500) errors are retried up to twice with
+/// exponential backoff; set [`RetryConfig::retry_on`] to choose exactly which errors retry.
+/// Streaming requests are not retried, and retries require the `runtime` feature.
+///
+/// # Examples
+///
+/// ```
+/// use anthropic_api::{Credentials, RetryConfig};
+///
+/// // Only retry when the API is overloaded.
+/// let retry = RetryConfig {
+///     retry_on: |error| error.error.error_type == "overloaded_error",
+///     ..RetryConfig::default()
+/// };
+/// let credentials = Credentials::new("your-api-key", "").with_retry(retry);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Maximum number of retries after the initial attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled after each subsequent attempt.
+    pub initial_backoff: Duration,
+    /// Decides whether a failed request should be retried.
+    pub retry_on: fn(&AnthropicErrorResponse) -> bool,
+}
+
+impl RetryConfig {
+    /// The default retry predicate: retries rate limit, overloaded, and internal API errors.
+    pub fn is_retryable(error: &AnthropicErrorResponse) -> bool {
+        matches!(
+            error.error.error_type.as_str(),
+            "rate_limit_error" | "overloaded_error" | "api_error"
+        )
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(500),
+            retry_on: RetryConfig::is_retryable,
+        }
+    }
+}
+
+/// Connection pool settings for the HTTP client used to send requests.
+///
+/// Build a client with [`ClientConfig::build`] and attach it to [`Credentials`] with
+/// [`Credentials::with_client`], so that requests share the client's pooled connections.
+/// Settings left as `None` keep reqwest's defaults. Not available on wasm32, where the
+/// browser manages connections.
+///
+/// # Examples
+///
+/// ```no_run
+/// use anthropic_api::{ClientConfig, Credentials};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), reqwest::Error> {
+/// // Drop idle connections before a gateway that closes them after 30 seconds does.
+/// let client = ClientConfig {
+///     pool_idle_timeout: Some(Duration::from_secs(20)),
+///     pool_max_idle_per_host: Some(8),
+/// }
+/// .build()?;
+///
+/// let credentials = Credentials::from_env().with_client(client);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ClientConfig {
+    /// Maximum number of idle connections kept open per host.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long an idle connection is kept open before it is closed.
+    pub pool_idle_timeout: Option<Duration>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ClientConfig {
+    /// Builds an HTTP client with these settings.
+    pub fn build(&self) -> Result<Client, reqwest::Error> {
+        let mut builder = Client::builder();
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        builder.build()
+    }
+}
+
+/// How the API key is sent to the API.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum AuthMethod {
+    /// Send the key in the `x-api-key` header (the default).
+    #[default]
+    ApiKey,
+    /// Send the key as a bearer token in the `Authorization` header, as expected by
+    /// OAuth flows and many gateways.
+    Bearer,
+}
 
 /// Holds the API key and base URL for an Anthropic-compatible API.
 ///
 /// This struct is used to authenticate requests to the Anthropic API.
 /// It can be created from environment variables or explicitly with an API key and base URL.
-#[derive(Clone, Eq, PartialEq)]
+///
+/// All fields are reference counted, so cloning credentials for each request does not copy
+/// the API key or any other strings. Request builders also accept `&Credentials`, so a shared
+/// set of credentials can be passed without an explicit clone.
+///
+/// # Example
+///
+/// ```
+/// use anthropic_api::{messages::MessagesBuilder, Credentials};
+///
+/// let credentials = Credentials::new("your-api-key", "");
+/// for prompt in ["Hello", "Goodbye"] {
+///     let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+///         .credentials(&credentials)
+///         .user_text(prompt)
+///         .build()
+///         .unwrap();
+///     assert_eq!(request.credentials.as_ref(), Some(&credentials));
+/// }
+/// ```
+#[derive(Clone)]
 pub struct Credentials {
-    api_key: String,
-    base_url: String,
+    api_key: Arc<str>,
+    base_url: Arc<str>,
+    client: Option<Arc<Client>>,
+    interceptor: Option<Arc<dyn RequestInterceptor>>,
+    observer: Option<Arc<dyn ResponseObserver>>,
+    retry: Option<Arc<RetryConfig>>,
+    version: Arc<str>,
+    user_agent: Arc<str>,
+    auth_method: AuthMethod,
+    organization_id: Option<Arc<str>>,
 }
 
 impl Credentials {
@@ -68,7 +448,7 @@ impl Credentials {
     ///
     /// let credentials = Credentials::new("your-api-key", "");
     /// ```
-    #[instrument(skip(api_key, base_url))]
+    #[cfg_attr(feature = "tracing", instrument(skip(api_key, base_url)))]
     pub fn new(api_key: impl Into<String>, base_url: impl Into<String>) -> Self {
         let base_url = base_url.into();
         let base_url = if base_url.is_empty() {
@@ -80,8 +460,16 @@ impl Credentials {
         };
         trace!("Credentials created with base URL: {}", base_url);
         Self {
-            api_key: api_key.into(),
-            base_url,
+            api_key: api_key.into().into(),
+            base_url: base_url.into(),
+            client: None,
+            interceptor: None,
+            observer: None,
+            retry: None,
+            version: DEFAULT_API_VERSION.into(),
+            user_agent: DEFAULT_USER_AGENT.into(),
+            auth_method: AuthMethod::ApiKey,
+            organization_id: None,
         }
     }
 
@@ -91,6 +479,7 @@ impl Credentials {
     ///
     /// This function will panic if the `ANTHROPIC_API_KEY` variable is missing from the environment.
     /// If only the `ANTHROPIC_BASE_URL` variable is missing, it will use the default URL.
+    /// Use [`Credentials::try_from_env`] to handle a missing key without panicking.
     ///
     /// # Examples
     ///
@@ -100,34 +489,72 @@ impl Credentials {
     /// // Assumes ANTHROPIC_API_KEY is set in the environment
     /// let credentials = Credentials::from_env();
     /// ```
-    #[instrument]
+    #[cfg_attr(feature = "tracing", instrument)]
     pub fn from_env() -> Credentials {
+        Self::try_from_env().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fetches the credentials from the environment variables `ANTHROPIC_API_KEY` and `ANTHROPIC_BASE_URL`,
+    /// returning an error instead of panicking if they are missing or invalid.
+    ///
+    /// If only the `ANTHROPIC_BASE_URL` variable is missing, it will use the default URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::Credentials;
+    ///
+    /// match Credentials::try_from_env() {
+    ///     Ok(credentials) => println!("Using {}", credentials.base_url()),
+    ///     Err(e) => eprintln!("Credentials not configured: {e}"),
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", instrument)]
+    pub fn try_from_env() -> Result<Credentials, CredentialsError> {
         debug!("Loading credentials from environment variables");
-        let api_key = match env::var("ANTHROPIC_API_KEY") {
+        Self::from_vars(
+            env::var("ANTHROPIC_API_KEY"),
+            env::var("ANTHROPIC_BASE_URL"),
+        )
+    }
+
+    /// Builds credentials from the values of the `ANTHROPIC_API_KEY` and `ANTHROPIC_BASE_URL` variables.
+    fn from_vars(
+        api_key: Result<String, VarError>,
+        base_url: Result<String, VarError>,
+    ) -> Result<Credentials, CredentialsError> {
+        let api_key = match api_key {
             Ok(key) => {
                 debug!("Found ANTHROPIC_API_KEY in environment");
                 key
             }
-            Err(_) => {
+            Err(VarError::NotPresent) => {
                 error!("ANTHROPIC_API_KEY not found in environment");
-                panic!("ANTHROPIC_API_KEY environment variable is required");
+                return Err(CredentialsError::MissingApiKey);
+            }
+            Err(VarError::NotUnicode(_)) => {
+                error!("ANTHROPIC_API_KEY is not valid unicode");
+                return Err(CredentialsError::NotUnicode {
+                    var: "ANTHROPIC_API_KEY",
+                });
             }
         };
 
-        let base_url_unparsed = env::var("ANTHROPIC_BASE_URL").unwrap_or_else(|e| match e {
-            VarError::NotPresent => {
+        let base_url_unparsed = match base_url {
+            Ok(base_url) => base_url,
+            Err(VarError::NotPresent) => {
                 debug!("ANTHROPIC_BASE_URL not found, using default");
                 DEFAULT_BASE_URL.clone()
             }
-            VarError::NotUnicode(v) => {
-                error!("ANTHROPIC_BASE_URL is not valid unicode: {v:#?}");
-                panic!("ANTHROPIC_BASE_URL is not unicode: {v:#?}");
+            Err(VarError::NotUnicode(_)) => {
+                error!("ANTHROPIC_BASE_URL is not valid unicode");
+                return Err(CredentialsError::NotUnicode {
+                    var: "ANTHROPIC_BASE_URL",
+                });
             }
-        });
+        };
 
-        let base_url = parse_base_url(base_url_unparsed);
-        debug!("Using base URL: {}", base_url);
-        Credentials { api_key, base_url }
+        Ok(Credentials::new(api_key, base_url_unparsed))
     }
 
     /// Returns the API key.
@@ -139,6 +566,286 @@ impl Credentials {
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
+
+    /// Sets the base URL, keeping the API key and every other setting.
+    ///
+    /// If the base URL is empty, it will use the default Anthropic API URL. Only
+    /// message requests have a per-request `base_url`; to route batch, model or Admin API
+    /// requests elsewhere, pass credentials built with this method to their builders.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::Credentials;
+    ///
+    /// let anthropic = Credentials::new("your-api-key", "");
+    /// let gateway = anthropic.clone().with_base_url("https://gateway.example.com/v1");
+    /// assert_eq!(gateway.base_url(), "https://gateway.example.com/v1/");
+    /// assert_eq!(gateway.api_key(), anthropic.api_key());
+    /// ```
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
+        self.base_url = if base_url.is_empty() {
+            DEFAULT_BASE_URL.clone()
+        } else {
+            parse_base_url(base_url)
+        }
+        .into();
+        self
+    }
+
+    /// Returns the API version sent in the `anthropic-version` header.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Sets the API version sent in the `anthropic-version` header.
+    ///
+    /// Defaults to [`DEFAULT_API_VERSION`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::Credentials;
+    ///
+    /// let credentials = Credentials::new("your-api-key", "").with_version("2023-06-01");
+    /// assert_eq!(credentials.version(), "2023-06-01");
+    /// ```
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into().into();
+        self
+    }
+
+    /// Returns the value sent in the `User-Agent` header.
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Sets the value sent in the `User-Agent` header.
+    ///
+    /// Defaults to [`DEFAULT_USER_AGENT`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::Credentials;
+    ///
+    /// let credentials = Credentials::new("your-api-key", "").with_user_agent("my-gateway/1.0");
+    /// assert_eq!(credentials.user_agent(), "my-gateway/1.0");
+    /// ```
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into().into();
+        self
+    }
+
+    /// Returns how the API key is sent to the API.
+    pub fn auth_method(&self) -> AuthMethod {
+        self.auth_method
+    }
+
+    /// Sets how the API key is sent to the API.
+    ///
+    /// Defaults to [`AuthMethod::ApiKey`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::{AuthMethod, Credentials};
+    ///
+    /// let credentials = Credentials::new("gateway-token", "https://gateway.example.com/v1/")
+    ///     .with_auth_method(AuthMethod::Bearer);
+    /// ```
+    pub fn with_auth_method(mut self, auth_method: AuthMethod) -> Self {
+        self.auth_method = auth_method;
+        self
+    }
+
+    /// Returns the organization sent in the `anthropic-organization-id` header, if any.
+    pub fn organization_id(&self) -> Option<&str> {
+        self.organization_id.as_deref()
+    }
+
+    /// Targets requests at the given organization with the `anthropic-organization-id` header.
+    ///
+    /// Needed for admin keys that can operate on more than one organization. The header is
+    /// not sent unless an organization is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::Credentials;
+    ///
+    /// let credentials = Credentials::new("your-admin-key", "")
+    ///     .with_organization_id("12345678-1234-5678-1234-567812345678");
+    /// assert_eq!(
+    ///     credentials.organization_id(),
+    ///     Some("12345678-1234-5678-1234-567812345678")
+    /// );
+    /// ```
+    pub fn with_organization_id(mut self, organization_id: impl Into<String>) -> Self {
+        self.organization_id = Some(organization_id.into().into());
+        self
+    }
+
+    /// Sends requests made with these credentials through the given HTTP client.
+    ///
+    /// By default, each request uses a new client built with [`Client::new`], which honors the
+    /// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY`, and `NO_PROXY` environment variables. Supply
+    /// your own client to configure an explicit [`reqwest::Proxy`], timeouts, or TLS settings,
+    /// or to reuse pooled connections across requests; [`ClientConfig`] builds one with
+    /// custom pool settings.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use anthropic_api::Credentials;
+    ///
+    /// # fn main() -> Result<(), reqwest::Error> {
+    /// let proxy = reqwest::Proxy::https("http://proxy.example.com:3128")?
+    ///     .basic_auth("proxy-user", "proxy-password");
+    /// let client = reqwest::Client::builder().proxy(proxy).build()?;
+    ///
+    /// let credentials = Credentials::from_env().with_client(client);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(Arc::new(client));
+        self
+    }
+
+    /// Returns the HTTP client to use for a request made with these credentials.
+    fn client(&self) -> Client {
+        match &self.client {
+            Some(client) => Client::clone(client),
+            None => Client::new(),
+        }
+    }
+
+    /// Attaches a [`RequestInterceptor`] that is run on every request made with these credentials.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::Credentials;
+    ///
+    /// let credentials = Credentials::new("your-api-key", "")
+    ///     .with_interceptor(|request: reqwest::RequestBuilder| {
+    ///         request.header("x-correlation-id", "abc123")
+    ///     });
+    /// ```
+    pub fn with_interceptor(mut self, interceptor: impl RequestInterceptor + 'static) -> Self {
+        self.interceptor = Some(Arc::new(interceptor));
+        self
+    }
+
+    /// Attaches a [`ResponseObserver`] that is called for every response received with these credentials.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anthropic_api::{Credentials, ResponseMetadata};
+    ///
+    /// let credentials = Credentials::new("your-api-key", "")
+    ///     .with_observer(|metadata: &ResponseMetadata| {
+    ///         println!("{} {:?} in {:?}", metadata.status, metadata.request_id, metadata.latency);
+    ///     });
+    /// ```
+    pub fn with_observer(mut self, observer: impl ResponseObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Enables automatic retries for requests made with these credentials.
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = Some(Arc::new(retry));
+        self
+    }
+
+    /// Sets the authentication, version, user agent, and content type headers on the request.
+    fn apply_headers(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = match self.auth_method {
+            AuthMethod::ApiKey => request.header("x-api-key", self.api_key()),
+            AuthMethod::Bearer => request.bearer_auth(self.api_key()),
+        };
+        let request = request
+            .header("anthropic-version", self.version())
+            .header(USER_AGENT, self.user_agent())
+            .header(CONTENT_TYPE, "application/json");
+        match self.organization_id() {
+            Some(organization_id) => request.header("anthropic-organization-id", organization_id),
+            None => request,
+        }
+    }
+
+    /// Applies the attached interceptor, if any, to the request.
+    fn intercept(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.interceptor {
+            Some(interceptor) => interceptor.before(request),
+            None => request,
+        }
+    }
+
+    /// Replaces every occurrence of the API key in `text` so it can be logged safely.
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    fn redact(&self, text: &str) -> String {
+        if self.api_key.is_empty() {
+            return text.to_string();
+        }
+        text.replace(self.api_key(), "[REDACTED_API_KEY]")
+    }
+}
+
+/// Headers whose values are credentials and must never be logged.
+const SENSITIVE_HEADERS: [&str; 3] = ["x-api-key", "authorization", "proxy-authorization"];
+
+/// Returns the headers as name/value pairs with credential values masked, for logging.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+fn redact_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                "[REDACTED]".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+impl PartialEq for Credentials {
+    fn eq(&self, other: &Self) -> bool {
+        self.api_key == other.api_key
+            && self.base_url == other.base_url
+            && self.version == other.version
+            && self.user_agent == other.user_agent
+            && self.auth_method == other.auth_method
+            && self.organization_id == other.organization_id
+            && same_hook(&self.client, &other.client)
+            && same_hook(&self.interceptor, &other.interceptor)
+            && same_hook(&self.observer, &other.observer)
+            && same_hook(&self.retry, &other.retry)
+    }
+}
+
+/// Compares two optional hooks by identity.
+fn same_hook<T: ?Sized>(a: &Option<Arc<T>>, b: &Option<Arc<T>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl Eq for Credentials {}
+
+/// Clones the reference-counted fields, sharing the underlying strings and hooks.
+impl From<&Credentials> for Credentials {
+    fn from(credentials: &Credentials) -> Self {
+        credentials.clone()
+    }
 }
 
 impl Debug for Credentials {
@@ -146,8 +853,8 @@ impl Debug for Credentials {
         // Redact the API key for security.
         write!(
             f,
-            "Credentials {{ api_key: [REDACTED], base_url: {} }}",
-            self.base_url
+            "Credentials {{ api_key: [REDACTED], base_url: {}, version: {}, user_agent: {}, auth_method: {:?}, organization_id: {:?} }}",
+            self.base_url, self.version, self.user_agent, self.auth_method, self.organization_id
         )
     }
 }
@@ -174,7 +881,7 @@ pub struct AnthropicErrorResponse {
 
 impl AnthropicErrorResponse {
     /// Creates a new error response with the given message and error type.
-    #[instrument]
+    #[cfg_attr(feature = "tracing", instrument)]
     fn new(message: String, error_type: String) -> AnthropicErrorResponse {
         warn!(%message, %error_type, "Creating error response");
         AnthropicErrorResponse {
@@ -195,27 +902,176 @@ impl std::fmt::Display for AnthropicErrorResponse {
 
 impl std::error::Error for AnthropicErrorResponse {}
 
+/// Converts a builder error, such as a missing required field, into an error response.
+fn builder_error(error: impl std::fmt::Display) -> AnthropicErrorResponse {
+    AnthropicErrorResponse::new(error.to_string(), "builder".to_string())
+}
+
+/// Runs `fetch` for every id with at most `concurrency` requests in flight, returning the
+/// results in the order of `ids`.
+///
+/// A `concurrency` of zero is treated as one.
+async fn fetch_many<I, T, F, Fut>(ids: I, concurrency: usize, fetch: F) -> Vec<T>
+where
+    I: IntoIterator,
+    F: FnMut(I::Item) -> Fut,
+    Fut: std::future::Future<Output = T>,
+{
+    use futures_util::StreamExt;
+
+    futures_util::stream::iter(ids)
+        .map(fetch)
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Returns a channel that yields the given error and then closes.
+///
+/// Used by methods that deliver their results on a channel when they fail before any
+/// request is made.
+#[cfg(feature = "runtime")]
+fn error_channel<T>(error: AnthropicErrorResponse) -> Receiver<ApiResponseOrError<T>> {
+    let (tx, rx) = channel(1);
+    let _ = tx.try_send(Err(error));
+    rx
+}
+
+/// Drives `future` to completion on a private current-thread runtime.
+///
+/// Backs the `create_blocking` methods. Panics if called from within an async runtime,
+/// like `reqwest::blocking`.
+#[cfg(feature = "blocking")]
+fn block_on<T>(
+    future: impl std::future::Future<Output = ApiResponseOrError<T>>,
+) -> ApiResponseOrError<T> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(future)
+}
+
 /// Represents a response from the Anthropic API, which can be either a success or an error.
-#[derive(Deserialize, Clone)]
+///
+/// # Examples
+///
+/// ```
+/// use anthropic_api::ApiResponse;
+///
+/// let response: ApiResponse<serde_json::Value> = serde_json::from_str(
+///     r#"{"type": "error", "error": {"type": "not_found_error", "message": "Not found"}}"#,
+/// )
+/// .unwrap();
+/// assert!(response.is_err());
+/// assert_eq!(response.into_result().unwrap_err().error.error_type, "not_found_error");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(untagged)]
 pub enum ApiResponse<T> {
     /// An error response.
-    Err { error: AnthropicErrorResponse },
+    Err {
+        #[serde(flatten)]
+        error: AnthropicErrorResponse,
+    },
     /// A successful response.
     Ok(T),
 }
 
+impl<T> ApiResponse<T> {
+    /// Converts the response into a [`Result`].
+    pub fn into_result(self) -> ApiResponseOrError<T> {
+        match self {
+            ApiResponse::Ok(value) => Ok(value),
+            ApiResponse::Err { error } => Err(error),
+        }
+    }
+
+    /// Returns true if the response is a success.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ApiResponse::Ok(_))
+    }
+
+    /// Returns true if the response is an error.
+    pub fn is_err(&self) -> bool {
+        !self.is_ok()
+    }
+
+    /// Returns the successful value, if any.
+    pub fn ok(self) -> Option<T> {
+        self.into_result().ok()
+    }
+
+    /// Returns the error, if any.
+    pub fn err(self) -> Option<AnthropicErrorResponse> {
+        self.into_result().err()
+    }
+}
+
+impl<T> From<ApiResponse<T>> for ApiResponseOrError<T> {
+    fn from(response: ApiResponse<T>) -> Self {
+        response.into_result()
+    }
+}
+
 /// Represents token usage statistics for a request and response.
-#[derive(Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+///
+/// Every field may be absent: streaming `message_delta` events report only the counts that
+/// changed, so missing counts deserialize as zero or `None`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Usage {
     /// Number of tokens in the input.
+    #[serde(default)]
     pub input_tokens: u32,
     /// Number of tokens in the output.
+    #[serde(default)]
     pub output_tokens: u32,
     /// Number of tokens used for cache creation, if applicable.
     pub cache_creation_input_tokens: Option<u32>,
     /// Number of tokens read from cache, if applicable.
     pub cache_read_input_tokens: Option<u32>,
+    /// The service tier the request was served with, if reported.
+    #[serde(default)]
+    pub service_tier: Option<String>,
+    /// Usage of server-side tools such as web search, if any were used.
+    #[serde(default)]
+    pub server_tool_use: Option<ServerToolUsage>,
+}
+
+impl Usage {
+    /// Merges the usage reported by a later streaming event into this value.
+    ///
+    /// In streaming mode the `message_start` event carries the input token counts and the
+    /// final `message_delta` event carries the cumulative output token count. Counts that
+    /// the later event reports replace the earlier ones; counts it omits (deserialized as
+    /// zero or `None`) are kept.
+    pub fn merge(&mut self, update: &Usage) {
+        if update.input_tokens > 0 {
+            self.input_tokens = update.input_tokens;
+        }
+        if update.output_tokens > 0 {
+            self.output_tokens = update.output_tokens;
+        }
+        if update.cache_creation_input_tokens.is_some() {
+            self.cache_creation_input_tokens = update.cache_creation_input_tokens;
+        }
+        if update.cache_read_input_tokens.is_some() {
+            self.cache_read_input_tokens = update.cache_read_input_tokens;
+        }
+        if update.service_tier.is_some() {
+            self.service_tier.clone_from(&update.service_tier);
+        }
+        if update.server_tool_use.is_some() {
+            self.server_tool_use = update.server_tool_use;
+        }
+    }
+}
+
+/// Usage of server-side tools.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ServerToolUsage {
+    /// Number of web search requests made.
+    #[serde(default)]
+    pub web_search_requests: u32,
 }
 
 /// Result type for Anthropic API responses.
@@ -228,6 +1084,27 @@ impl From<reqwest::Error> for AnthropicErrorResponse {
     }
 }
 
+impl From<CredentialsError> for AnthropicErrorResponse {
+    fn from(value: CredentialsError) -> Self {
+        error!(error = %value, "Credentials error occurred");
+        AnthropicErrorResponse::new(value.to_string(), "credentials".to_string())
+    }
+}
+
+impl From<serde_json::Error> for AnthropicErrorResponse {
+    fn from(value: serde_json::Error) -> Self {
+        error!(error = %value, "JSON error occurred");
+        AnthropicErrorResponse::new(value.to_string(), "json".to_string())
+    }
+}
+
+impl From<CannotCloneRequestError> for AnthropicErrorResponse {
+    fn from(value: CannotCloneRequestError) -> Self {
+        error!(error = %value, "Failed to create event source");
+        AnthropicErrorResponse::new(value.to_string(), "stream".to_string())
+    }
+}
+
 impl From<std::io::Error> for AnthropicErrorResponse {
     fn from(value: std::io::Error) -> Self {
         error!(error = %value, "IO error occurred");
@@ -237,32 +1114,124 @@ impl From<std::io::Error> for AnthropicErrorResponse {
 
 /// Makes a request to the Anthropic API and deserializes the JSON response.
 ///
-/// This function logs the raw API response for debugging while ensuring sensitive data remains redacted.
-#[instrument(skip(builder, credentials_opt), fields(route = %route))]
+/// If the credentials carry a [`RetryConfig`], failed requests are retried according to it.
 async fn anthropic_request_json<F, T>(
     method: Method,
     route: &str,
     builder: F,
     credentials_opt: Option<Credentials>,
 ) -> ApiResponseOrError<T>
+where
+    F: Fn(RequestBuilder) -> RequestBuilder,
+    T: DeserializeOwned,
+{
+    anthropic_request_json_with_headers(method, route, builder, credentials_opt)
+        .await
+        .map(|(value, _)| value)
+}
+
+/// Like [`anthropic_request_json`], but also returns the headers of the successful response.
+#[cfg_attr(feature = "tracing", instrument(skip(builder, credentials_opt), fields(route = %route)))]
+async fn anthropic_request_json_with_headers<F, T>(
+    method: Method,
+    route: &str,
+    builder: F,
+    credentials_opt: Option<Credentials>,
+) -> ApiResponseOrError<(T, HeaderMap)>
+where
+    F: Fn(RequestBuilder) -> RequestBuilder,
+    T: DeserializeOwned,
+{
+    let credentials = resolve_credentials(credentials_opt)?;
+    #[cfg(feature = "runtime")]
+    if let Some(retry) = credentials.retry.clone() {
+        return anthropic_request_json_retrying(&retry, method, route, builder, credentials).await;
+    }
+    anthropic_request_json_once(method, route, builder, credentials).await
+}
+
+/// Makes a request to the Anthropic API, retrying failures according to `retry`.
+#[cfg(feature = "runtime")]
+async fn anthropic_request_json_retrying<F, T>(
+    retry: &RetryConfig,
+    method: Method,
+    route: &str,
+    builder: F,
+    credentials: Credentials,
+) -> ApiResponseOrError<(T, HeaderMap)>
+where
+    F: Fn(RequestBuilder) -> RequestBuilder,
+    T: DeserializeOwned,
+{
+    let mut backoff = retry.initial_backoff;
+    let mut attempt = 0;
+    loop {
+        match anthropic_request_json_once(method.clone(), route, &builder, credentials.clone())
+            .await
+        {
+            Err(error) if attempt < retry.max_retries && (retry.retry_on)(&error) => {
+                attempt += 1;
+                warn!(
+                    attempt,
+                    error_type = %error.error.error_type,
+                    backoff_ms = backoff.as_millis() as u64,
+                    "Retrying failed request"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Makes a single request to the Anthropic API and deserializes the JSON response.
+///
+/// This function logs the raw API response for debugging while ensuring sensitive data remains redacted.
+/// The response headers are returned alongside the deserialized body.
+async fn anthropic_request_json_once<F, T>(
+    method: Method,
+    route: &str,
+    builder: F,
+    credentials: Credentials,
+) -> ApiResponseOrError<(T, HeaderMap)>
 where
     F: FnOnce(RequestBuilder) -> RequestBuilder,
     T: DeserializeOwned,
 {
     debug!(?method, "Making JSON request to Anthropic API");
-    let response = anthropic_request(method, route, builder, credentials_opt).await?;
+    let response = anthropic_request(method, route, builder, Some(credentials.clone())).await?;
+
+    let status = response.status();
+    let headers = response.headers().clone();
 
     // Log the raw response body for debugging.
-    let response_text = response.text().await?;
-    debug!(response_body = %response_text, "Raw API response");
+    let response_text = read_body(response).await?;
+    debug!(response_body = %credentials.redact(&response_text), "Raw API response");
 
     // Parse the response text back to JSON.
     let api_response: ApiResponse<T> = match serde_json::from_str(&response_text) {
         Ok(parsed) => parsed,
+        // Proxies and load balancers can fail with non-JSON bodies, such as an HTML 502 page.
+        Err(_) if !status.is_success() => {
+            warn!(status = %status, "Received non-JSON error response");
+            return Err(AnthropicErrorResponse::new(
+                format!(
+                    "Request failed with status {}: {}",
+                    status,
+                    body_snippet(&credentials.redact(&response_text))
+                ),
+                "http".to_string(),
+            ));
+        }
         Err(e) => {
-            error!(error = %e, response_text = %response_text, "Failed to parse API response");
+            error!(error = %e, response_text = %credentials.redact(&response_text), "Failed to parse API response");
             return Err(AnthropicErrorResponse::new(
-                format!("Failed to parse API response: {}", e),
+                format!(
+                    "Failed to parse API response ({} bytes): {}",
+                    response_text.len(),
+                    e
+                ),
                 "json_parse_error".to_string(),
             ));
         }
@@ -271,7 +1240,7 @@ where
     match api_response {
         ApiResponse::Ok(t) => {
             info!("Successfully received and parsed JSON response");
-            Ok(t)
+            Ok((t, headers))
         }
         ApiResponse::Err { error } => {
             warn!(error_type = %error.error.error_type, message = %error.error.message, "Received error response from API");
@@ -280,10 +1249,73 @@ where
     }
 }
 
+/// Reads the whole response body as text.
+///
+/// A connection that fails part way through the body is reported as an `io` error with the
+/// number of bytes received, so it is not mistaken for a malformed response.
+async fn read_body(response: Response) -> ApiResponseOrError<String> {
+    use futures_util::StreamExt;
+
+    let mut body = Vec::new();
+    let mut chunks = response.bytes_stream();
+    while let Some(chunk) = chunks.next().await {
+        match chunk {
+            Ok(chunk) => body.extend_from_slice(&chunk),
+            Err(e) => {
+                error!(error = %e, bytes_read = body.len(), "Failed to read response body");
+                return Err(AnthropicErrorResponse::new(
+                    format!(
+                        "Failed to read response body after {} bytes: {}",
+                        body.len(),
+                        e
+                    ),
+                    "io".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Returns the start of a response body for use in an error message.
+fn body_snippet(body: &str) -> String {
+    const MAX_CHARS: usize = 200;
+    let body = body.trim();
+    match body.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// Converts an unsuccessful response into an error response.
+///
+/// The body is usually the API's JSON error; anything else is reported as an `http` error,
+/// with the API key redacted from the body snippet.
+async fn error_from_response(
+    response: Response,
+    credentials: &Credentials,
+) -> AnthropicErrorResponse {
+    let status = response.status();
+    let body = match read_body(response).await {
+        Ok(body) => body,
+        Err(e) => return e,
+    };
+    serde_json::from_str(&body).unwrap_or_else(|_| {
+        AnthropicErrorResponse::new(
+            format!(
+                "Request failed with status {}: {}",
+                status,
+                body_snippet(&credentials.redact(&body))
+            ),
+            "http".to_string(),
+        )
+    })
+}
+
 /// Makes a request to the Anthropic API.
 ///
 /// This function logs only non-sensitive details (method and URL) to avoid exposing confidential data.
-#[instrument(skip(builder, credentials_opt), fields(route = %route))]
+#[cfg_attr(feature = "tracing", instrument(skip(builder, credentials_opt), fields(route = %route)))]
 async fn anthropic_request<F>(
     method: Method,
     route: &str,
@@ -294,11 +1326,9 @@ where
     F: FnOnce(RequestBuilder) -> RequestBuilder,
 {
     debug!(?method, "Making request to Anthropic API");
-    let client = Client::new();
-    let credentials =
-        credentials_opt.unwrap_or_else(|| DEFAULT_CREDENTIALS.read().unwrap().clone());
-    let base_url = credentials.base_url();
-    let url = format!("{}{route}", base_url);
+    let credentials = resolve_credentials(credentials_opt)?;
+    let client = credentials.client();
+    let url = endpoint_url(credentials.base_url(), route)?;
     trace!(url = %url, "Constructed full URL");
 
     let mut request = client.request(method.clone(), url.clone());
@@ -307,16 +1337,25 @@ where
     // Log safe request details.
     debug!(method = ?method, url = %url, "Request details");
 
-    trace!("Sending request with headers");
-    let response = request
-        .header("x-api-key", credentials.api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header(CONTENT_TYPE, "application/json")
-        .send()
-        .await?;
+    let request = credentials.apply_headers(request);
+    let (client, request) = credentials.intercept(request).build_split();
+    let request = request?;
+    trace!(headers = ?redact_headers(request.headers()), "Sending request with headers");
+    // `Instant` is not available on wasm32, where latency is reported as zero.
+    #[cfg(not(target_arch = "wasm32"))]
+    let started = Instant::now();
+    let response = client.execute(request).await?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let latency = started.elapsed();
+    #[cfg(target_arch = "wasm32")]
+    let latency = Duration::ZERO;
+
+    if let Some(observer) = &credentials.observer {
+        observer.after(&ResponseMetadata::from_response(method, &response, latency));
+    }
 
     let status = response.status();
-    debug!(status = %status, headers = ?response.headers(), "Response headers");
+    debug!(status = %status, headers = ?redact_headers(response.headers()), "Response headers");
 
     if status.is_success() {
         info!(status = %status, "Request successful");
@@ -330,13 +1369,13 @@ where
 /// Creates an event source for streaming responses from the Anthropic API.
 ///
 /// This function ensures that only safe-to-log information (method and URL) is included.
-#[instrument(skip(builder, credentials_opt), fields(route = %route))]
+#[cfg_attr(feature = "tracing", instrument(skip(builder, credentials), fields(route = %route)))]
 async fn anthropic_request_stream<F>(
     method: Method,
     route: &str,
     builder: F,
-    credentials_opt: Option<Credentials>,
-) -> Result<EventSource, CannotCloneRequestError>
+    credentials: Credentials,
+) -> ApiResponseOrError<EventSource>
 where
     F: FnOnce(RequestBuilder) -> RequestBuilder,
 {
@@ -344,11 +1383,8 @@ where
         ?method,
         "Creating event source for streaming from Anthropic API"
     );
-    let client = Client::new();
-    let credentials =
-        credentials_opt.unwrap_or_else(|| DEFAULT_CREDENTIALS.read().unwrap().clone());
-    let base_url = credentials.base_url();
-    let url = format!("{}{route}", base_url);
+    let client = credentials.client();
+    let url = endpoint_url(credentials.base_url(), route)?;
     trace!(url = %url, "Constructed full URL for streaming");
 
     let mut request = client.request(method.clone(), url.clone());
@@ -358,11 +1394,8 @@ where
     debug!(method = ?method, url = %url, "Streaming request details");
 
     trace!("Creating event source");
-    let stream = request
-        .header("x-api-key", credentials.api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header(CONTENT_TYPE, "application/json")
-        .eventsource()?;
+    let request = credentials.apply_headers(request);
+    let stream = credentials.intercept(request).eventsource()?;
 
     info!("Successfully created event source for streaming");
     Ok(stream)
@@ -370,39 +1403,137 @@ where
 
 /// Makes a POST request to the Anthropic API with the given JSON payload.
 ///
-/// This function logs the payload after redacting sensitive data.
-#[instrument(skip(json, credentials_opt), fields(route = %route))]
+/// This function logs the payload after redacting sensitive data. The response headers
+/// are returned alongside the deserialized body.
+#[cfg_attr(feature = "tracing", instrument(skip(json, credentials_opt), fields(route = %route)))]
 async fn anthropic_post<J, T>(
     route: &str,
     json: &J,
+    betas: &[String],
     credentials_opt: Option<Credentials>,
-) -> ApiResponseOrError<T>
+) -> ApiResponseOrError<(T, HeaderMap)>
 where
     J: Serialize + ?Sized,
     T: DeserializeOwned,
 {
     debug!("Making POST request to Anthropic API");
     // Log the payload with sensitive data redacted.
-    if let Ok(json_str) = serde_json::to_string(json) {
-        let default_creds = DEFAULT_CREDENTIALS.read().unwrap();
-        let credentials = credentials_opt.as_ref().unwrap_or(&default_creds);
-        let redacted_json = json_str.replace(credentials.api_key(), "[REDACTED_API_KEY]");
-        debug!(payload = %redacted_json, "POST request payload");
+    #[cfg(feature = "tracing")]
+    if let (Ok(json_str), Ok(credentials)) = (
+        serde_json::to_string(json),
+        resolve_credentials(credentials_opt.clone()),
+    ) {
+        debug!(payload = %credentials.redact(&json_str), "POST request payload");
     }
 
-    anthropic_request_json(
+    anthropic_request_json_with_headers(
         Method::POST,
         route,
-        |request| request.json(json),
+        |request| with_betas(request.json(json), betas),
         credentials_opt,
     )
     .await
 }
 
+/// Sends a request to any API route and deserializes the JSON response.
+///
+/// This is an escape hatch for endpoints that don't have a typed wrapper yet. The route is
+/// joined onto the credentials' base URL, and the request gets the same authentication,
+/// version, and user agent headers, interceptor, observer, retries, and logging as the
+/// typed API. If `credentials` is `None`, the default credentials are used.
+///
+/// # Example
+///
+/// ```no_run
+/// # use anthropic_api::{request_json, Credentials};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let usage: serde_json::Value = request_json(
+///     reqwest::Method::POST,
+///     "messages/count_tokens",
+///     Some(serde_json::json!({
+///         "model": "claude-3-7-sonnet-20250219",
+///         "messages": [{"role": "user", "content": "Hello, Claude!"}]
+///     })),
+///     Some(Credentials::from_env()),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn request_json<T: DeserializeOwned>(
+    method: Method,
+    route: &str,
+    body: Option<serde_json::Value>,
+    credentials: Option<Credentials>,
+) -> ApiResponseOrError<T> {
+    anthropic_request_json(
+        method,
+        route,
+        |request| match &body {
+            Some(body) => request.json(body),
+            None => request,
+        },
+        credentials,
+    )
+    .await
+}
+
+/// Opens a server-sent events stream from any API route.
+///
+/// Like [`request_json`], this uses the same headers and interceptor as the typed API, but
+/// returns the raw [`EventSource`] so the caller can handle the events itself. Retries are
+/// not attempted.
+pub async fn request_stream(
+    method: Method,
+    route: &str,
+    body: Option<serde_json::Value>,
+    credentials: Option<Credentials>,
+) -> ApiResponseOrError<EventSource> {
+    let credentials = resolve_credentials(credentials)?;
+    anthropic_request_stream(
+        method,
+        route,
+        |request| match body {
+            Some(body) => request.json(&body),
+            None => request,
+        },
+        credentials,
+    )
+    .await
+}
+
+/// Sets the `anthropic-beta` header to the comma-joined beta flags, if there are any.
+fn with_betas(request: RequestBuilder, betas: &[String]) -> RequestBuilder {
+    if betas.is_empty() {
+        request
+    } else {
+        request.header("anthropic-beta", betas.join(","))
+    }
+}
+
+/// Joins a route onto the base URL.
+///
+/// Routes are relative to the base URL, so leading slashes are ignored rather than
+/// replacing the base URL's path: `/messages` against `https://gateway.example.com/anthropic/v1/`
+/// resolves to `https://gateway.example.com/anthropic/v1/messages`. This relies on
+/// [`parse_base_url`] having given the base URL a trailing slash.
+fn endpoint_url(base_url: &str, route: &str) -> ApiResponseOrError<reqwest::Url> {
+    reqwest::Url::parse(base_url)
+        .and_then(|base| base.join(route.trim_start_matches('/')))
+        .map_err(|e| {
+            AnthropicErrorResponse::new(
+                format!("Invalid URL for base {base_url:?} and route {route:?}: {e}"),
+                "url".to_string(),
+            )
+        })
+}
+
 /// Ensures the base URL ends with a trailing slash.
 ///
-/// This function adds a trailing slash if not already present to avoid URL construction errors.
-#[instrument]
+/// This function adds a trailing slash if not already present, so that [`endpoint_url`]
+/// appends routes to the base URL's path instead of replacing its last segment.
+#[cfg_attr(feature = "tracing", instrument)]
 fn parse_base_url(mut value: String) -> String {
     trace!(original_url = %value, "Parsing base URL");
     if !value.ends_with('/') {
@@ -416,6 +1547,486 @@ fn parse_base_url(mut value: String) -> String {
 /// Test utilities.
 #[cfg(test)]
 pub mod tests {
+    use super::*;
+    pub use crate::testing::{http_response, json_response, sse_response, MockServer};
+    use std::sync::Mutex;
+
     /// Default model to use in tests.
     pub const DEFAULT_LEGACY_MODEL: &str = "claude-3-5-sonnet-20240620";
+
+    #[tokio::test]
+    async fn test_request_interceptor_adds_header() {
+        let server = MockServer::start(vec![json_response("200 OK", r#"{"ok": true}"#)]).await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str()).with_interceptor(
+            |request: RequestBuilder| request.header("x-correlation-id", "abc123"),
+        );
+
+        let response: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials))
+                .await
+                .unwrap();
+
+        assert_eq!(response, serde_json::json!({"ok": true}));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /v1/models "));
+        assert!(requests[0].contains("x-correlation-id: abc123"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_api_version_header() {
+        let server = MockServer::start(vec![
+            json_response("200 OK", r#"{"ok": true}"#),
+            json_response("200 OK", r#"{"ok": true}"#),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let _: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials.clone()))
+                .await
+                .unwrap();
+        let _: serde_json::Value = anthropic_request_json(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials.with_version("2099-01-01")),
+        )
+        .await
+        .unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0].contains("anthropic-version: 2023-06-01\r\n"));
+        assert!(requests[1].contains("anthropic-version: 2099-01-01\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_header() {
+        let server = MockServer::start(vec![
+            json_response("200 OK", r#"{"ok": true}"#),
+            json_response("200 OK", r#"{"ok": true}"#),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let _: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials.clone()))
+                .await
+                .unwrap();
+        let _: serde_json::Value = anthropic_request_json(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials.with_user_agent("my-gateway/1.0")),
+        )
+        .await
+        .unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0].contains(&format!("user-agent: {DEFAULT_USER_AGENT}\r\n")));
+        assert!(requests[1].contains("user-agent: my-gateway/1.0\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_organization_id_header() {
+        let server = MockServer::start(vec![
+            json_response("200 OK", r#"{"ok": true}"#),
+            json_response("200 OK", r#"{"ok": true}"#),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let _: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials.clone()))
+                .await
+                .unwrap();
+        let _: serde_json::Value = anthropic_request_json(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials.with_organization_id("org_01")),
+        )
+        .await
+        .unwrap();
+
+        let requests = server.requests();
+        assert!(!requests[0].contains("anthropic-organization-id"));
+        assert!(requests[1].contains("anthropic-organization-id: org_01\r\n"));
+    }
+
+    #[test]
+    fn test_credentials_from_ref_shares_strings() {
+        let credentials = Credentials::new("test-key", "").with_organization_id("org_01");
+        let request =
+            crate::messages::MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+                .credentials(&credentials)
+                .user_text("Hi")
+                .build()
+                .unwrap();
+
+        let shared = request.credentials.unwrap();
+        assert_eq!(shared, credentials);
+        assert!(Arc::ptr_eq(&shared.api_key, &credentials.api_key));
+        assert!(Arc::ptr_eq(
+            shared.organization_id.as_ref().unwrap(),
+            credentials.organization_id.as_ref().unwrap()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bearer_auth_method() {
+        let server = MockServer::start(vec![json_response("200 OK", r#"{"ok": true}"#)]).await;
+        let credentials = Credentials::new("gateway-token", server.base_url.as_str())
+            .with_auth_method(AuthMethod::Bearer);
+
+        let _: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials))
+                .await
+                .unwrap();
+
+        let request = &server.requests()[0];
+        assert!(request.contains("authorization: Bearer gateway-token\r\n"));
+        assert!(!request.contains("x-api-key"));
+    }
+
+    #[test]
+    fn test_api_response_helpers() {
+        let ok: ApiResponse<serde_json::Value> = serde_json::from_str(r#"{"id": "1"}"#).unwrap();
+        assert!(ok.is_ok());
+        assert_eq!(ok.clone().ok(), Some(serde_json::json!({"id": "1"})));
+        assert_eq!(ok.clone().err(), None);
+        assert_eq!(
+            ApiResponseOrError::from(ok),
+            Ok(serde_json::json!({"id": "1"}))
+        );
+
+        let err: ApiResponse<serde_json::Value> = serde_json::from_str(
+            r#"{"type": "error", "error": {"type": "rate_limit_error", "message": "Slow down"}}"#,
+        )
+        .unwrap();
+        assert!(err.is_err());
+        assert_eq!(err.clone().ok(), None);
+        let error = err.into_result().unwrap_err();
+        assert_eq!(error.error.error_type, "rate_limit_error");
+        assert_eq!(error.error.message, "Slow down");
+    }
+
+    #[test]
+    fn test_set_default_credentials() {
+        let credentials = Credentials::new("runtime-key", "https://example.com/v1/");
+        let previous = default_credentials();
+        set_default_credentials(credentials.clone());
+        let (default, resolved) = (default_credentials(), resolve_credentials(None));
+        // Restore before asserting so other tests never resolve to the test credentials.
+        *DEFAULT_CREDENTIALS.write().unwrap() = previous;
+
+        assert_eq!(default, Some(credentials.clone()));
+        assert_eq!(resolved, Ok(credentials));
+    }
+
+    #[tokio::test]
+    async fn test_non_json_error_body() {
+        let page = format!("<html><body>{}</body></html>", "Bad Gateway ".repeat(50));
+        let server = MockServer::start(vec![http_response(
+            "502 Bad Gateway",
+            &[("content-type", "text/html")],
+            &page,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let error = anthropic_request_json::<_, serde_json::Value>(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.error.error_type, "http");
+        assert!(error
+            .error
+            .message
+            .starts_with("Request failed with status 502 Bad Gateway: <html><body>Bad Gateway"));
+        assert!(error.error.message.ends_with("..."));
+        assert!(error.error.message.len() < page.len());
+    }
+
+    #[tokio::test]
+    async fn test_requests_use_proxy_from_client() {
+        let proxy = MockServer::start(vec![json_response("200 OK", r#"{"ok": true}"#)]).await;
+        let client = Client::builder()
+            .proxy(reqwest::Proxy::http(proxy.base_url.as_str()).unwrap())
+            .build()
+            .unwrap();
+        let credentials =
+            Credentials::new("test-key", "http://api.example.invalid/v1/").with_client(client);
+
+        let _: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials))
+                .await
+                .unwrap();
+
+        let requests = proxy.requests();
+        assert!(requests[0].starts_with("GET http://api.example.invalid/v1/models "));
+    }
+
+    #[tokio::test]
+    async fn test_client_config() {
+        let server = MockServer::start(vec![
+            json_response("200 OK", r#"{"ok": true}"#),
+            json_response("200 OK", r#"{"ok": true}"#),
+        ])
+        .await;
+        let client = ClientConfig {
+            pool_max_idle_per_host: Some(0),
+            pool_idle_timeout: Some(Duration::from_secs(1)),
+        }
+        .build()
+        .unwrap();
+        let credentials =
+            Credentials::new("test-key", server.base_url.as_str()).with_client(client);
+
+        for _ in 0..2 {
+            let _: serde_json::Value =
+                anthropic_request_json(Method::GET, "models", |r| r, Some(credentials.clone()))
+                    .await
+                    .unwrap();
+        }
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_body_is_io_error() {
+        let server = MockServer::start(vec![
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 100\r\nconnection: close\r\n\r\n{\"id\": \"msg".to_string(),
+            json_response("200 OK", r#"{"id": "msg"#),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let error = anthropic_request_json::<_, serde_json::Value>(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials.clone()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.error.error_type, "io");
+        assert!(error.error.message.contains("after 11 bytes"));
+
+        let error = anthropic_request_json::<_, serde_json::Value>(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.error.error_type, "json_parse_error");
+        assert!(error.error.message.contains("(11 bytes)"));
+    }
+
+    #[tokio::test]
+    async fn test_request_json() {
+        let server =
+            MockServer::start(vec![json_response("200 OK", r#"{"input_tokens": 12}"#)]).await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let response: serde_json::Value = request_json(
+            Method::POST,
+            "messages/count_tokens",
+            Some(serde_json::json!({"model": "claude-3-7-sonnet-20250219"})),
+            Some(credentials),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["input_tokens"], 12);
+
+        let request = &server.requests()[0];
+        assert!(request.starts_with("POST /v1/messages/count_tokens "));
+        assert!(request.contains("x-api-key: test-key"));
+        assert!(request.ends_with(r#"{"model":"claude-3-7-sonnet-20250219"}"#));
+    }
+
+    #[test]
+    fn test_endpoint_url() {
+        let base_url = parse_base_url("https://gateway.example.com/anthropic/v1".to_string());
+        for route in ["messages", "/messages", "//messages"] {
+            assert_eq!(
+                endpoint_url(&base_url, route).unwrap().as_str(),
+                "https://gateway.example.com/anthropic/v1/messages"
+            );
+        }
+        assert_eq!(
+            endpoint_url(&base_url, "models?limit=2").unwrap().as_str(),
+            "https://gateway.example.com/anthropic/v1/models?limit=2"
+        );
+        let error = endpoint_url("not a url/", "messages").unwrap_err();
+        assert_eq!(error.error.error_type, "url");
+    }
+
+    #[test]
+    fn test_credentials_clone_shares_strings() {
+        let credentials = Credentials::new("test-key", "");
+        let clone = credentials.clone();
+        assert!(Arc::ptr_eq(&credentials.api_key, &clone.api_key));
+        assert!(Arc::ptr_eq(&credentials.base_url, &clone.base_url));
+        assert_eq!(credentials, clone);
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        let error: AnthropicErrorResponse = serde_json::from_str::<serde_json::Value>("{")
+            .unwrap_err()
+            .into();
+        assert_eq!(error.error.error_type, "json");
+
+        let error: AnthropicErrorResponse = CannotCloneRequestError.into();
+        assert_eq!(error.error.error_type, "stream");
+    }
+
+    #[test]
+    fn test_redaction() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "sk-secret".parse().unwrap());
+        headers.insert("authorization", "Bearer sk-secret".parse().unwrap());
+        headers.insert("anthropic-version", "2023-06-01".parse().unwrap());
+
+        let redacted = redact_headers(&headers);
+        assert!(redacted.contains(&("x-api-key".to_string(), "[REDACTED]".to_string())));
+        assert!(redacted.contains(&("authorization".to_string(), "[REDACTED]".to_string())));
+        assert!(redacted.contains(&("anthropic-version".to_string(), "2023-06-01".to_string())));
+
+        let credentials = Credentials::new("sk-secret", "");
+        assert_eq!(
+            credentials.redact(r#"{"key": "sk-secret"}"#),
+            r#"{"key": "[REDACTED_API_KEY]"}"#
+        );
+        assert_eq!(Credentials::new("", "").redact("body"), "body");
+    }
+
+    #[test]
+    fn test_credentials_from_vars() {
+        let credentials =
+            Credentials::from_vars(Ok("test-key".to_string()), Err(VarError::NotPresent)).unwrap();
+        assert_eq!(credentials.api_key(), "test-key");
+        assert_eq!(credentials.base_url(), DEFAULT_BASE_URL.as_str());
+
+        assert_eq!(
+            Credentials::from_vars(Err(VarError::NotPresent), Err(VarError::NotPresent)),
+            Err(CredentialsError::MissingApiKey)
+        );
+
+        let error: AnthropicErrorResponse = CredentialsError::MissingApiKey.into();
+        assert_eq!(error.error.error_type, "credentials");
+    }
+
+    fn api_error(status: &str, error_type: &str) -> String {
+        json_response(
+            status,
+            &format!(
+                r#"{{"type": "error", "error": {{"type": "{error_type}", "message": "{status}"}}}}"#
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_api_error_response_is_returned_as_error() {
+        let server = MockServer::start(vec![api_error("404 Not Found", "not_found_error")]).await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let error = anthropic_request_json::<_, serde_json::Value>(
+            Method::GET,
+            "models/unknown",
+            |r| r,
+            Some(credentials),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.response_type, "error");
+        assert_eq!(error.error.error_type, "not_found_error");
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_retry_predicate() {
+        let retry = RetryConfig {
+            initial_backoff: Duration::from_millis(1),
+            retry_on: |error| error.error.error_type == "overloaded_error",
+            ..RetryConfig::default()
+        };
+
+        // An overloaded error is retried until the request succeeds.
+        let server = MockServer::start(vec![
+            api_error("529 Overloaded", "overloaded_error"),
+            json_response("200 OK", r#"{"ok": true}"#),
+        ])
+        .await;
+        let credentials =
+            Credentials::new("test-key", server.base_url.as_str()).with_retry(retry.clone());
+        let response: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials))
+                .await
+                .unwrap();
+        assert_eq!(response, serde_json::json!({"ok": true}));
+        assert_eq!(server.requests().len(), 2);
+
+        // A 400 is not retried.
+        let server = MockServer::start(vec![
+            api_error("400 Bad Request", "invalid_request_error"),
+            json_response("200 OK", r#"{"ok": true}"#),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str()).with_retry(retry);
+        let error = anthropic_request_json::<_, serde_json::Value>(
+            Method::GET,
+            "models",
+            |r| r,
+            Some(credentials),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.error.error_type, "invalid_request_error");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_response_observer_receives_metadata() {
+        let server = MockServer::start(vec![http_response(
+            "200 OK",
+            &[
+                ("content-type", "application/json"),
+                ("request-id", "req_123"),
+                ("anthropic-ratelimit-requests-remaining", "49"),
+            ],
+            r#"{"ok": true}"#,
+        )])
+        .await;
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let sink = observed.clone();
+        let credentials = Credentials::new("test-key", server.base_url.as_str()).with_observer(
+            move |metadata: &ResponseMetadata| sink.lock().unwrap().push(metadata.clone()),
+        );
+
+        let _: serde_json::Value =
+            anthropic_request_json(Method::GET, "models", |r| r, Some(credentials))
+                .await
+                .unwrap();
+
+        let observed = observed.lock().unwrap();
+        assert_eq!(observed.len(), 1);
+        assert_eq!(observed[0].status, StatusCode::OK);
+        assert_eq!(observed[0].request_id.as_deref(), Some("req_123"));
+        assert_eq!(
+            observed[0].rate_limit_headers,
+            vec![(
+                "anthropic-ratelimit-requests-remaining".to_string(),
+                "49".to_string()
+            )]
+        );
+    }
 }
@@ -7,7 +7,7 @@
 //!
 //! - Send messages to Claude models and receive responses
 //! - Support for streaming responses
-//! - Tool usage capabilities
+//! - Tool usage capabilities, including an automatic tool execution loop
 //! - Image input support
 //!
 //! ## Basic Usage
@@ -21,10 +21,7 @@
 //!
 //!     let response =MessagesBuilder::builder(
 //!         "claude-3-7-sonnet-20250219",
-//!         vec![Message {
-//!             role: MessageRole::User,
-//!             content: MessageContent::Text("Hello, Claude!".to_string()),
-//!         }],
+//!         vec![Message::user("Hello, Claude!")],
 //!         1024,
 //!     )
 //!     .credentials(credentials)
@@ -36,21 +33,38 @@
 //! }
 //! ```
 
-use crate::{anthropic_post, anthropic_request_stream, ApiResponseOrError, Credentials, Usage};
-use anyhow::Result;
+#[cfg(feature = "blocking")]
+use crate::block_on;
+#[cfg(feature = "runtime")]
+use crate::error_channel;
+use crate::{
+    anthropic_post, anthropic_request_stream, builder_error, resolve_credentials, with_betas,
+    AnthropicError, AnthropicErrorResponse, ApiResponseOrError, Credentials, Usage,
+};
+use base64::prelude::{Engine, BASE64_STANDARD};
 use derive_builder::Builder;
-use futures_util::StreamExt;
+#[cfg(any(feature = "runtime", feature = "cancellation"))]
+use futures_util::future::{self, Either};
+use futures_util::future::{join_all, BoxFuture};
+use futures_util::{FutureExt, Stream, StreamExt};
 use reqwest::Method;
-use reqwest_eventsource::{CannotCloneRequestError, Event, EventSource};
-use serde::{Deserialize, Serialize};
+use reqwest_eventsource::{Event, EventSource};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "runtime")]
 use tokio::sync::mpsc::{channel, Receiver, Sender};
+#[cfg(feature = "cancellation")]
+use tokio_util::sync::CancellationToken;
 
 /// Represents a full message response from the Anthropic API.
 ///
 /// This struct contains the complete response from a message request, including
 /// the model's generated content and usage statistics.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct MessagesResponse {
     /// Unique identifier for this message
     pub id: String,
@@ -61,7 +75,7 @@ pub struct MessagesResponse {
     /// The content blocks in the response (text, tool use, thinking, redacted thinking)
     pub content: Vec<ResponseContentBlock>,
     /// Reason why the model stopped generating, if applicable
-    pub stop_reason: Option<String>,
+    pub stop_reason: Option<StopReason>,
     /// The specific sequence that caused generation to stop, if applicable
     pub stop_sequence: Option<String>,
     /// The type of the response (always "message")
@@ -69,18 +83,125 @@ pub struct MessagesResponse {
     pub typ: String,
     /// Token usage statistics for the request and response
     pub usage: Usage,
+    /// The code execution container used for this request, if the code execution tool ran
+    #[serde(default)]
+    pub container: Option<Container>,
+    /// Log probability data, if the API returns any.
+    ///
+    /// The Messages API does not currently document a logprobs field, so this is
+    /// kept as raw JSON and is `None` unless the response carries a `logprobs` key.
+    #[serde(default)]
+    pub logprobs: Option<Value>,
+    /// Value of the `request-id` response header, useful when reporting issues to Anthropic.
+    ///
+    /// Only set for responses returned by [`MessagesResponse::create`]; responses assembled
+    /// from a stream do not carry it.
+    #[serde(skip)]
+    pub request_id: Option<String>,
+    /// Value of the `anthropic-organization-id` response header.
+    #[serde(skip)]
+    pub organization_id: Option<String>,
+}
+
+impl MessagesResponse {
+    /// Value of the object `type` field for messages.
+    pub const TYPE: &'static str = "message";
+}
+
+/// Reason why the model stopped generating.
+///
+/// Unrecognized values are preserved in [`StopReason::Other`] so that new
+/// reasons added by the API don't break deserialization.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StopReason {
+    /// The model reached a natural stopping point
+    EndTurn,
+    /// The request's `max_tokens` limit was reached
+    MaxTokens,
+    /// One of the custom stop sequences was generated
+    StopSequence,
+    /// The model invoked one or more tools
+    ToolUse,
+    /// A long-running turn was paused and can be continued
+    PauseTurn,
+    /// The model declined to respond
+    Refusal,
+    /// Generation filled the model's context window before reaching `max_tokens`
+    ModelContextWindowExceeded,
+    /// A stop reason not known to this version of the library
+    Other(String),
+}
+
+impl StopReason {
+    /// Returns the stop reason as it appears in the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            StopReason::EndTurn => "end_turn",
+            StopReason::MaxTokens => "max_tokens",
+            StopReason::StopSequence => "stop_sequence",
+            StopReason::ToolUse => "tool_use",
+            StopReason::PauseTurn => "pause_turn",
+            StopReason::Refusal => "refusal",
+            StopReason::ModelContextWindowExceeded => "model_context_window_exceeded",
+            StopReason::Other(reason) => reason,
+        }
+    }
+}
+
+impl From<&str> for StopReason {
+    fn from(value: &str) -> Self {
+        match value {
+            "end_turn" => StopReason::EndTurn,
+            "max_tokens" => StopReason::MaxTokens,
+            "stop_sequence" => StopReason::StopSequence,
+            "tool_use" => StopReason::ToolUse,
+            "pause_turn" => StopReason::PauseTurn,
+            "refusal" => StopReason::Refusal,
+            "model_context_window_exceeded" => StopReason::ModelContextWindowExceeded,
+            other => StopReason::Other(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for StopReason {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Ok(StopReason::from(value.as_str()))
+    }
+}
+
+impl Serialize for StopReason {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 /// Content block in a response, can be text or tool use.
 ///
 /// Claude's responses can contain different types of content blocks.
 /// Currently, this can be either text, a tool use request, a thinking block, or a redacted thinking block.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(tag = "type")]
 pub enum ResponseContentBlock {
     /// A text content block containing natural language
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        /// Sources supporting the text, present when citations are enabled on a document
+        citations: Option<Vec<Citation>>,
+    },
     /// A tool use request from the model
     #[serde(rename = "tool_use")]
     ToolUse {
@@ -94,13 +215,203 @@ pub enum ResponseContentBlock {
     /// A redacted thinking block from the model
     #[serde(rename = "redacted_thinking")]
     RedactedThinking { data: String },
+    /// A server tool call, such as code execution, run by the API rather than the caller
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// The result of running code with the code execution tool
+    #[serde(rename = "code_execution_tool_result")]
+    CodeExecutionToolResult {
+        tool_use_id: String,
+        content: CodeExecutionResult,
+    },
+    /// The pages found by the web search tool
+    #[serde(rename = "web_search_tool_result")]
+    WebSearchToolResult {
+        tool_use_id: String,
+        content: WebSearchToolResultContent,
+    },
+}
+
+/// Outcome of a code execution tool call.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum CodeExecutionResult {
+    /// The code ran; a non-zero `return_code` means it failed
+    #[serde(rename = "code_execution_result")]
+    Result {
+        stdout: String,
+        stderr: String,
+        return_code: i32,
+        /// Files created by the code, as returned by the API
+        #[serde(default)]
+        content: Vec<Value>,
+    },
+    /// The code could not be run, for example `unavailable` or `execution_time_exceeded`
+    #[serde(rename = "code_execution_tool_result_error")]
+    Error { error_code: String },
+}
+
+/// Outcome of a web search tool call: the pages found, or an error.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum WebSearchToolResultContent {
+    /// The pages the search returned
+    Results(Vec<WebSearchResult>),
+    /// The search could not be run
+    Error(WebSearchToolResultError),
+}
+
+/// A page returned by the web search tool.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type", rename = "web_search_result")]
+pub struct WebSearchResult {
+    /// URL of the page
+    pub url: String,
+    /// Title of the page
+    pub title: String,
+    /// Page content, encrypted; pass it back unchanged in later turns
+    pub encrypted_content: String,
+    /// How recently the page was updated, if known (e.g. "April 30, 2025")
+    #[serde(default)]
+    pub page_age: Option<String>,
+}
+
+/// A failed web search, for example `max_uses_exceeded` or `too_many_requests`.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type", rename = "web_search_tool_result_error")]
+pub struct WebSearchToolResultError {
+    /// Reason the search failed
+    pub error_code: String,
+}
+
+/// The sandbox container in which the code execution tool runs.
+///
+/// Pass its `id` back as the request's `container` to reuse the same container, and its
+/// files, in a later request.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Container {
+    /// Identifier of the container
+    pub id: String,
+    /// When the container expires, as an RFC 3339 timestamp
+    pub expires_at: crate::timestamp::Timestamp,
+}
+
+impl Container {
+    /// Returns `expires_at` as a [`SystemTime`](std::time::SystemTime), if it is a valid
+    /// RFC 3339 timestamp.
+    pub fn expires_at_time(&self) -> Option<std::time::SystemTime> {
+        crate::timestamp::to_system_time(&self.expires_at)
+    }
+}
+
+/// Writes the text of text blocks, and a compact bracketed summary of other blocks, such as
+/// `[tool_use calculator {"operands":[15,27]}]`.
+impl std::fmt::Display for ResponseContentBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponseContentBlock::Text { text, .. } => f.write_str(text),
+            ResponseContentBlock::ToolUse { name, input, .. } => {
+                write!(f, "[tool_use {} {}]", name, input)
+            }
+            ResponseContentBlock::Thinking { .. } => f.write_str("[thinking]"),
+            ResponseContentBlock::RedactedThinking { .. } => f.write_str("[redacted_thinking]"),
+            ResponseContentBlock::ServerToolUse { name, input, .. } => {
+                write!(f, "[server_tool_use {} {}]", name, input)
+            }
+            ResponseContentBlock::CodeExecutionToolResult { content, .. } => match content {
+                CodeExecutionResult::Result { return_code, .. } => {
+                    write!(f, "[code_execution_result {}]", return_code)
+                }
+                CodeExecutionResult::Error { error_code } => {
+                    write!(f, "[code_execution_tool_result_error {}]", error_code)
+                }
+            },
+            ResponseContentBlock::WebSearchToolResult { content, .. } => match content {
+                WebSearchToolResultContent::Results(results) => {
+                    write!(f, "[web_search_tool_result {} results]", results.len())
+                }
+                WebSearchToolResultContent::Error(error) => {
+                    write!(f, "[web_search_tool_result_error {}]", error.error_code)
+                }
+            },
+        }
+    }
+}
+
+/// A reference from a response text block into a source document or search result.
+///
+/// Citation types added by the API after this release deserialize as [`Citation::Other`].
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum Citation {
+    /// A character range in a plain text document
+    #[serde(rename = "char_location")]
+    CharLocation {
+        /// The text being cited
+        cited_text: String,
+        /// Index of the document among the request's document blocks
+        document_index: u64,
+        /// Title of the document, if it has one
+        document_title: Option<String>,
+        /// Index of the first cited character (inclusive)
+        start_char_index: u64,
+        /// Index after the last cited character (exclusive)
+        end_char_index: u64,
+    },
+    /// A page range in a PDF document
+    #[serde(rename = "page_location")]
+    PageLocation {
+        /// The text being cited
+        cited_text: String,
+        /// Index of the document among the request's document blocks
+        document_index: u64,
+        /// Title of the document, if it has one
+        document_title: Option<String>,
+        /// Number of the first cited page, starting at 1 (inclusive)
+        start_page_number: u64,
+        /// Number after the last cited page (exclusive)
+        end_page_number: u64,
+    },
+    /// A range of content blocks in a custom content document
+    #[serde(rename = "content_block_location")]
+    ContentBlockLocation {
+        /// The text being cited
+        cited_text: String,
+        /// Index of the document among the request's document blocks
+        document_index: u64,
+        /// Title of the document, if it has one
+        document_title: Option<String>,
+        /// Index of the first cited content block (inclusive)
+        start_block_index: u64,
+        /// Index after the last cited content block (exclusive)
+        end_block_index: u64,
+    },
+    /// A page returned by the web search tool
+    #[serde(rename = "web_search_result_location")]
+    WebSearchResultLocation {
+        /// The text being cited
+        cited_text: String,
+        /// URL of the cited page
+        url: String,
+        /// Title of the cited page, if it has one
+        title: Option<String>,
+        /// Reference to the cited passage; pass it back unchanged in later turns
+        encrypted_index: String,
+    },
+    /// A citation type not known to this version of the library, kept as raw JSON
+    #[serde(untagged)]
+    Other(Value),
 }
 
 /// Streaming events from the Anthropic API.
 ///
 /// When using streaming mode, the API returns a series of events that
 /// incrementally build up the complete response.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(tag = "type")]
 pub enum StreamEvent {
     /// Indicates the start of a message
@@ -122,18 +433,30 @@ pub enum StreamEvent {
     #[serde(rename = "content_block_stop")]
     ContentBlockStop { index: u32 },
     /// Contains final message information like stop reason
+    ///
+    /// `usage` carries only the counts the event reports; anything omitted is zero or `None`.
     #[serde(rename = "message_delta")]
-    MessageDelta { delta: MessageDelta, usage: Usage },
+    MessageDelta {
+        delta: MessageDelta,
+        #[serde(default)]
+        usage: Usage,
+    },
     /// Indicates the end of the message
     #[serde(rename = "message_stop")]
     MessageStop,
     /// A keepalive event that can be ignored
     #[serde(rename = "ping")]
     Ping,
+    /// An error that occurred after the stream started (e.g. `overloaded_error`)
+    #[serde(rename = "error")]
+    Error { error: AnthropicError },
+    /// An event type not known to this version of the library, which can be ignored
+    #[serde(other)]
+    Unknown,
 }
 
 /// Initial message information in a streaming response.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct MessageStart {
     /// Unique identifier for this message
     pub id: String,
@@ -143,39 +466,87 @@ pub struct MessageStart {
     pub role: MessageRole,
     /// Initial content blocks in the response
     pub content: Vec<ContentBlockStart>,
+    /// Initial token usage, including the input token counts
+    #[serde(default)]
+    pub usage: Usage,
 }
 
 /// Initial content block in a streaming response.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
-#[serde(untagged)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
 pub enum ContentBlockStart {
     /// A text content block
+    #[serde(rename = "text")]
     Text { text: String },
     /// A tool use request
+    #[serde(rename = "tool_use")]
     ToolUse {
         id: String,
         name: String,
         input: Value,
     },
+    /// A thinking block; the signature arrives later as a `SignatureDelta`
+    #[serde(rename = "thinking")]
+    Thinking {
+        thinking: String,
+        #[serde(default)]
+        signature: String,
+    },
+    /// A redacted thinking block
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+    /// A server tool call; its input arrives as `InputJsonDelta`s like a tool use
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// A code execution result, sent complete in the start event
+    #[serde(rename = "code_execution_tool_result")]
+    CodeExecutionToolResult {
+        tool_use_id: String,
+        content: CodeExecutionResult,
+    },
+    /// A web search result, sent complete in the start event
+    #[serde(rename = "web_search_tool_result")]
+    WebSearchToolResult {
+        tool_use_id: String,
+        content: WebSearchToolResultContent,
+    },
 }
 
 /// Incremental update to a content block in a streaming response.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
-#[serde(untagged)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
 pub enum ContentBlockDelta {
     /// Text delta for a text content block
+    #[serde(rename = "text_delta")]
     Text { text: String },
     /// JSON delta for a tool use input
+    #[serde(rename = "input_json_delta")]
     InputJsonDelta { partial_json: String },
+    /// Thinking delta for a thinking content block
+    #[serde(rename = "thinking_delta")]
+    ThinkingDelta { thinking: String },
+    /// Signature for a thinking content block, sent just before the block stops
+    #[serde(rename = "signature_delta")]
+    SignatureDelta { signature: String },
+    /// Citation supporting a text content block, sent when citations are enabled
+    #[serde(rename = "citations_delta")]
+    CitationsDelta { citation: Citation },
 }
 
 /// Final message information in a streaming response.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct MessageDelta {
     /// Reason why the model stopped generating, if applicable
-    pub stop_reason: Option<String>,
+    pub stop_reason: Option<StopReason>,
     /// The specific sequence that caused generation to stop, if applicable
     pub stop_sequence: Option<String>,
+    /// The code execution container used for this request, if any
+    #[serde(default)]
+    pub container: Option<Container>,
 }
 
 /// Request to the Anthropic Messages API.
@@ -209,7 +580,7 @@ pub struct MessagesRequest {
     /// System prompt to guide the assistant's behavior.
     #[builder(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub system: Option<String>,
+    pub system: Option<SystemPrompt>,
     /// Sampling temperature (0.0 to 1.0).
     #[builder(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -229,16 +600,207 @@ pub struct MessagesRequest {
     #[builder(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_k: Option<u32>,
-    /// Top-p (nucleus) sampling parameter.
+    /// Top-p (nucleus) sampling parameter (0.0 to 1.0).
     #[builder(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f64>,
+    /// Service tier to use for the request.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
+    /// ID of a code execution [`Container`] from an earlier response, to reuse its files.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// Beta features to enable, sent comma-joined in the `anthropic-beta` header (not serialized).
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub betas: Option<Vec<String>>,
+    /// Maximum time to wait between streamed events, including pings, before the stream
+    /// fails with a `stream` error (not serialized). Requires the `runtime` feature.
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub stream_idle_timeout: Option<Duration>,
+    /// Base URL to send this request to instead of the credentials' base URL (not serialized).
+    ///
+    /// The API key and all other credential settings are kept, so requests can be routed to
+    /// a compatible backend without a second set of credentials. Other request builders
+    /// take their base URL from the credentials; see [`Credentials::with_base_url`].
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub base_url: Option<String>,
     /// Credentials for authentication (not serialized).
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl MessagesRequest {
+    /// Maximum number of stop sequences the API accepts in a single request.
+    pub const MAX_STOP_SEQUENCES: usize = 8191;
+
+    /// Checks that the conversation is non-empty, the sampling parameters are in range, and
+    /// there are at most [`Self::MAX_STOP_SEQUENCES`] stop sequences, each containing
+    /// non-whitespace text.
+    ///
+    /// Requests are validated before they are sent, so an empty `messages` list, an
+    /// out-of-range `temperature`, `top_p`, or `top_k`, or too many or blank stop sequences
+    /// fail fast with a `validation` error instead of a 400 from the API. To prefill the
+    /// assistant's reply, end `messages` with an assistant turn rather than leaving it empty.
+    ///
+    /// Setting both `temperature` and `top_p` is not rejected: the API accepts the
+    /// combination and only recommends adjusting one of them, so the choice is left to
+    /// the caller.
+    pub fn validate(&self) -> ApiResponseOrError<()> {
+        if self.messages.is_empty() {
+            return Err(validation_error("messages must not be empty".to_string()));
+        }
+        let unit_range = |name: &str, value: Option<f64>| match value {
+            Some(value) if !(0.0..=1.0).contains(&value) => Err(validation_error(format!(
+                "{name} must be between 0 and 1, got {value}"
+            ))),
+            _ => Ok(()),
+        };
+        unit_range("temperature", self.temperature)?;
+        unit_range("top_p", self.top_p)?;
+        if self.top_k == Some(0) {
+            return Err(validation_error("top_k must be at least 1".to_string()));
+        }
+        if self
+            .stop_sequences
+            .iter()
+            .flatten()
+            .any(|s| s.trim().is_empty())
+        {
+            return Err(validation_error(
+                "stop_sequences must not contain empty or whitespace-only sequences".to_string(),
+            ));
+        }
+        if let Some(count) = self
+            .stop_sequences
+            .as_ref()
+            .map(Vec::len)
+            .filter(|&count| count > Self::MAX_STOP_SEQUENCES)
+        {
+            return Err(validation_error(format!(
+                "stop_sequences must contain at most {} sequences, got {count}",
+                Self::MAX_STOP_SEQUENCES
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the beta flags to send, adding any that the request's features require.
+    fn effective_betas(&self) -> Vec<String> {
+        let mut betas = self.betas.clone().unwrap_or_default();
+        let extended_cache = match &self.system {
+            Some(SystemPrompt::Blocks(blocks)) => blocks
+                .iter()
+                .filter_map(|block| block.cache_control.as_ref())
+                .any(CacheControl::is_extended),
+            _ => false,
+        };
+        if extended_cache && !betas.iter().any(|b| b == CacheControl::EXTENDED_TTL_BETA) {
+            betas.push(CacheControl::EXTENDED_TTL_BETA.to_string());
+        }
+        for beta in self.tools.iter().flatten().filter_map(Tool::required_beta) {
+            if !betas.iter().any(|b| b == beta) {
+                betas.push(beta.to_string());
+            }
+        }
+        betas
+    }
+
+    /// Returns the credentials to send the request with, applying the `base_url` override.
+    fn effective_credentials(&self) -> ApiResponseOrError<Credentials> {
+        let credentials = resolve_credentials(self.credentials.clone())?;
+        Ok(match &self.base_url {
+            Some(base_url) => credentials.with_base_url(base_url.as_str()),
+            None => credentials,
+        })
+    }
+}
+
+/// Body of a token counting request.
+///
+/// The request is serialized exactly as for the Messages API and then restricted to the
+/// fields the token counting endpoint accepts, so the counted prompt cannot drift from the
+/// one that is sent.
+struct CountTokensBody<'a>(&'a MessagesRequest);
+
+impl CountTokensBody<'_> {
+    /// Request fields accepted by the token counting endpoint.
+    const FIELDS: &'static [&'static str] = &[
+        "model",
+        "messages",
+        "system",
+        "tools",
+        "tool_choice",
+        "thinking",
+    ];
+}
+
+impl Serialize for CountTokensBody<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut body = serde_json::to_value(self.0).map_err(serde::ser::Error::custom)?;
+        if let Value::Object(fields) = &mut body {
+            fields.retain(|name, _| Self::FIELDS.contains(&name.as_str()));
+        }
+        body.serialize(serializer)
+    }
+}
+
+/// Response from the token counting endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MessageTokensCount {
+    /// Total number of input tokens across messages, system prompt, and tools
+    pub input_tokens: u64,
+}
+
+impl MessageTokensCount {
+    /// Counts the input tokens of a message request without sending it.
+    ///
+    /// The `model`, `messages`, `system`, `tools`, `tool_choice`, and `thinking` fields are
+    /// serialized exactly as [`MessagesResponse::create`] sends them, including any
+    /// `cache_control`, and the same beta headers are sent. Generation parameters such as
+    /// `max_tokens` and `stream` are not part of the count.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .system("You are a helpful assistant.")
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .build()?;
+    ///
+    /// let count = MessageTokensCount::create(&request).await?;
+    /// println!("{} input tokens", count.input_tokens);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(request: &MessagesRequest) -> ApiResponseOrError<Self> {
+        request.validate()?;
+        let betas = request.effective_betas();
+        let (count, _) = anthropic_post(
+            "messages/count_tokens",
+            &CountTokensBody(request),
+            &betas,
+            Some(request.effective_credentials()?),
+        )
+        .await?;
+        Ok(count)
+    }
+}
+
+/// Creates an error for a request that fails local validation.
+fn validation_error(message: String) -> AnthropicErrorResponse {
+    AnthropicErrorResponse::new(message, "validation".to_string())
+}
+
 /// Message in the conversation.
 ///
 /// Represents a single message in the conversation history,
@@ -251,6 +813,48 @@ pub struct Message {
     pub content: MessageContent,
 }
 
+impl Message {
+    /// Creates a message from the user.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let message = Message::user(
+    ///     ContentBuilder::new()
+    ///         .text("What is in this image?")
+    ///         .image(ImageSource {
+    ///             source_type: "base64".to_string(),
+    ///             media_type: MediaType::Png,
+    ///             data: "iVBORw0KGgo...".to_string(),
+    ///         }),
+    /// );
+    /// assert_eq!(message.role, MessageRole::User);
+    /// ```
+    pub fn user(content: impl Into<MessageContent>) -> Self {
+        Self {
+            role: MessageRole::User,
+            content: content.into(),
+        }
+    }
+
+    /// Creates a message from the assistant.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let message = Message::assistant("Hello!");
+    /// assert_eq!(message.content, MessageContent::Text("Hello!".to_string()));
+    /// ```
+    pub fn assistant(content: impl Into<MessageContent>) -> Self {
+        Self {
+            role: MessageRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
 /// Role of the message sender.
 ///
 /// In the Messages API, messages can be from either the user or the assistant.
@@ -276,9 +880,81 @@ pub enum MessageContent {
     ContentBlocks(Vec<RequestContentBlock>),
 }
 
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+/// Builds [`MessageContent`] from a sequence of content blocks.
+///
+/// # Example
+///
+/// ```
+/// # use anthropic_api::messages::*;
+/// let content: MessageContent = ContentBuilder::new()
+///     .document(DocumentSource {
+///         source_type: "text".to_string(),
+///         media_type: MediaType::PlainText,
+///         data: "The grass is green.".to_string(),
+///     })
+///     .text("What color is the grass?")
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ContentBuilder {
+    blocks: Vec<RequestContentBlock>,
+}
+
+impl ContentBuilder {
+    /// Creates an empty content builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a text block.
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.block(RequestContentBlock::Text { text: text.into() })
+    }
+
+    /// Appends an image block.
+    pub fn image(self, source: ImageSource) -> Self {
+        self.block(RequestContentBlock::Image { source })
+    }
+
+    /// Appends a document block.
+    pub fn document(self, source: DocumentSource) -> Self {
+        self.block(RequestContentBlock::Document { source })
+    }
+
+    /// Appends any content block.
+    pub fn block(mut self, block: RequestContentBlock) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// Returns the content blocks as [`MessageContent::ContentBlocks`].
+    pub fn build(self) -> MessageContent {
+        MessageContent::ContentBlocks(self.blocks)
+    }
+}
+
+impl From<ContentBuilder> for MessageContent {
+    fn from(builder: ContentBuilder) -> Self {
+        builder.build()
+    }
+}
+
 /// Content block in a request.
 ///
-/// Request content blocks can be either text or images.
+/// Request content blocks can be text, images, tool use and results, or thinking blocks
+/// echoed back from a previous assistant turn.
 #[derive(Serialize, Debug, Clone, Eq, PartialEq)]
 #[serde(tag = "type")]
 pub enum RequestContentBlock {
@@ -288,6 +964,87 @@ pub enum RequestContentBlock {
     /// An image content block
     #[serde(rename = "image")]
     Image { source: ImageSource },
+    /// A document content block, such as a PDF or plain text
+    #[serde(rename = "document")]
+    Document { source: DocumentSource },
+    /// A tool use request previously made by the assistant
+    #[serde(rename = "tool_use")]
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// The result of running a tool requested by the assistant
+    #[serde(rename = "tool_result")]
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        is_error: Option<bool>,
+    },
+    /// A thinking block previously returned by the assistant, including its signature
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    /// A redacted thinking block previously returned by the assistant
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+    /// A server tool call previously made by the assistant
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// A code execution result previously returned by the assistant
+    #[serde(rename = "code_execution_tool_result")]
+    CodeExecutionToolResult {
+        tool_use_id: String,
+        content: CodeExecutionResult,
+    },
+    /// A web search result previously returned by the assistant
+    #[serde(rename = "web_search_tool_result")]
+    WebSearchToolResult {
+        tool_use_id: String,
+        content: WebSearchToolResultContent,
+    },
+}
+
+impl From<ResponseContentBlock> for RequestContentBlock {
+    fn from(block: ResponseContentBlock) -> Self {
+        match block {
+            ResponseContentBlock::Text { text, .. } => RequestContentBlock::Text { text },
+            ResponseContentBlock::ToolUse { id, name, input } => {
+                RequestContentBlock::ToolUse { id, name, input }
+            }
+            ResponseContentBlock::Thinking {
+                signature,
+                thinking,
+            } => RequestContentBlock::Thinking {
+                thinking,
+                signature,
+            },
+            ResponseContentBlock::RedactedThinking { data } => {
+                RequestContentBlock::RedactedThinking { data }
+            }
+            ResponseContentBlock::ServerToolUse { id, name, input } => {
+                RequestContentBlock::ServerToolUse { id, name, input }
+            }
+            ResponseContentBlock::CodeExecutionToolResult {
+                tool_use_id,
+                content,
+            } => RequestContentBlock::CodeExecutionToolResult {
+                tool_use_id,
+                content,
+            },
+            ResponseContentBlock::WebSearchToolResult {
+                tool_use_id,
+                content,
+            } => RequestContentBlock::WebSearchToolResult {
+                tool_use_id,
+                content,
+            },
+        }
+    }
 }
 
 /// Source of an image content block.
@@ -298,82 +1055,863 @@ pub struct ImageSource {
     /// The type of image source (currently only "base64" is supported)
     #[serde(rename = "type")]
     pub source_type: String,
-    /// The MIME type of the image (e.g., "image/png", "image/jpeg")
-    pub media_type: String,
+    /// The MIME type of the image
+    pub media_type: MediaType,
     /// The base64-encoded image data
     pub data: String,
 }
 
-#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
-pub enum ThinkingType {
-    /// Whether Claude is to use thinking
-    #[serde(rename = "enabled")]
-    Enabled,
-    /// Whether Claude is not to use thinking
-    #[serde(rename = "disabled")]
-    Disabled,
+impl ImageSource {
+    /// Creates a base64 image source from the raw bytes of an image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let source = ImageSource::from_bytes(MediaType::Png, b"\x89PNG");
+    /// assert_eq!(source.data, "iVBORw==");
+    /// ```
+    pub fn from_bytes(media_type: MediaType, bytes: impl AsRef<[u8]>) -> Self {
+        Self {
+            source_type: "base64".to_string(),
+            media_type,
+            data: BASE64_STANDARD.encode(bytes),
+        }
+    }
+
+    /// Reads an image file into a base64 image source.
+    ///
+    /// The media type is inferred from the file extension (`.jpg`, `.jpeg`, `.png`, `.gif`,
+    /// or `.webp`); any other extension is an [`std::io::ErrorKind::InvalidInput`] error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::messages::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// let content: MessageContent = ContentBuilder::new()
+    ///     .image(ImageSource::from_file("chart.png")?)
+    ///     .text("What does this chart show?")
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let media_type = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(MediaType::from_extension)
+            .filter(|media_type| media_type.as_str().starts_with("image/"))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Unsupported image file type: {}", path.display()),
+                )
+            })?;
+        Ok(Self::from_bytes(media_type, std::fs::read(path)?))
+    }
 }
 
+/// Source of a document content block.
 #[derive(Serialize, Debug, Clone, Eq, PartialEq)]
-pub struct Thinking {
+pub struct DocumentSource {
+    /// The type of document source ("base64" for PDFs, "text" for plain text)
     #[serde(rename = "type")]
-    pub thinking_type: ThinkingType,
-    /// The budget for the thinking in tokens must
-    /// be at least 1024 and less than max_tokens
-    #[serde(rename = "budget_tokens")]
-    pub budget_tokens: u64,
+    pub source_type: String,
+    /// The MIME type of the document
+    pub media_type: MediaType,
+    /// The document data, base64-encoded for PDFs
+    pub data: String,
 }
 
-/// Tool definition.
+/// MIME type of an image or document source.
 ///
-/// Tools allow Claude to perform actions outside its context,
-/// such as calculations or API calls.
-#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
-pub struct Tool {
-    /// The name of the tool
-    pub name: String,
-    /// A description of what the tool does
-    pub description: String,
-    /// JSON Schema defining the input format for the tool
-    pub input_schema: Value,
-}
-
-/// Tool choice specification.
+/// Serializes to the exact string the API expects, such as `image/jpeg`.
 ///
-/// Controls how Claude decides whether to use tools.
-#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
-#[serde(tag = "type")]
-pub enum ToolChoice {
-    /// Claude decides whether to use tools
-    #[serde(rename = "auto")]
-    Auto,
-    /// Claude can use any available tool
-    #[serde(rename = "any")]
-    Any,
-    /// Claude must use the specified tool
-    #[serde(rename = "tool")]
-    Tool { name: String },
-    /// Claude must not use any tools
-    #[serde(rename = "none")]
-    None,
+/// # Example
+///
+/// ```
+/// # use anthropic_api::messages::MediaType;
+/// assert_eq!(MediaType::from_extension("JPG"), Some(MediaType::Jpeg));
+/// assert_eq!(MediaType::Jpeg.as_str(), "image/jpeg");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum MediaType {
+    /// `image/jpeg`
+    Jpeg,
+    /// `image/png`
+    Png,
+    /// `image/gif`
+    Gif,
+    /// `image/webp`
+    Webp,
+    /// `application/pdf`
+    Pdf,
+    /// `text/plain`
+    PlainText,
+    /// Any other MIME type, sent as is
+    Custom(String),
+}
+
+impl MediaType {
+    /// Returns the media type for a file extension, ignoring case and a leading dot.
+    ///
+    /// Returns `None` if the extension is not one of the supported types.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+        match extension.as_str() {
+            "jpg" | "jpeg" => Some(MediaType::Jpeg),
+            "png" => Some(MediaType::Png),
+            "gif" => Some(MediaType::Gif),
+            "webp" => Some(MediaType::Webp),
+            "pdf" => Some(MediaType::Pdf),
+            "txt" => Some(MediaType::PlainText),
+            _ => None,
+        }
+    }
+
+    /// Returns the MIME type string sent to the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            MediaType::Jpeg => "image/jpeg",
+            MediaType::Png => "image/png",
+            MediaType::Gif => "image/gif",
+            MediaType::Webp => "image/webp",
+            MediaType::Pdf => "application/pdf",
+            MediaType::PlainText => "text/plain",
+            MediaType::Custom(media_type) => media_type,
+        }
+    }
+}
+
+impl std::fmt::Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for MediaType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Extended thinking configuration.
+///
+/// Serialized as `{"type": "enabled", "budget_tokens": ...}` or `{"type": "disabled"}`.
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Thinking {
+    /// Claude thinks before answering
+    Enabled {
+        /// The budget for the thinking in tokens must
+        /// be at least 1024 and less than max_tokens
+        budget_tokens: u64,
+    },
+    /// Claude answers without thinking
+    Disabled,
+}
+
+/// System prompt, either plain text or text blocks that can be cached.
+///
+/// Plain strings convert into [`SystemPrompt::Text`], so the builder's `system` setter
+/// accepts them directly.
+///
+/// # Example
+///
+/// ```
+/// # use anthropic_api::messages::*;
+/// let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+///     .system(SystemPrompt::Blocks(vec![
+///         SystemBlock::text("You are a contracts lawyer."),
+///         SystemBlock::text("<the full text of a long contract>")
+///             .cache_control(CacheControl::ephemeral_1h()),
+///     ]));
+/// ```
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum SystemPrompt {
+    /// A plain text system prompt
+    Text(String),
+    /// System prompt text blocks
+    Blocks(Vec<SystemBlock>),
+}
+
+impl From<String> for SystemPrompt {
+    fn from(text: String) -> Self {
+        SystemPrompt::Text(text)
+    }
+}
+
+impl From<&str> for SystemPrompt {
+    fn from(text: &str) -> Self {
+        SystemPrompt::Text(text.to_string())
+    }
+}
+
+/// A text block of a [`SystemPrompt`].
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct SystemBlock {
+    /// The block type (always "text")
+    #[serde(rename = "type")]
+    pub block_type: String,
+    /// The text of the block
+    pub text: String,
+    /// Marks the end of a cacheable prompt prefix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+impl SystemBlock {
+    /// Creates a text block without cache control.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            block_type: "text".to_string(),
+            text: text.into(),
+            cache_control: None,
+        }
+    }
+
+    /// Caches the prompt up to and including this block.
+    pub fn cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = Some(cache_control);
+        self
+    }
+}
+
+/// Prompt caching breakpoint.
+///
+/// The prompt prefix up to the block carrying this is cached for the block's TTL.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct CacheControl {
+    /// The cache type (currently always "ephemeral")
+    #[serde(rename = "type")]
+    pub cache_type: String,
+    /// How long the cache lives: "5m" (the default when omitted) or "1h"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<String>,
+}
+
+impl CacheControl {
+    /// The beta flag required for the 1 hour cache TTL.
+    pub const EXTENDED_TTL_BETA: &'static str = "extended-cache-ttl-2025-04-11";
+
+    /// An ephemeral cache with the default 5 minute TTL.
+    pub fn ephemeral() -> Self {
+        Self {
+            cache_type: "ephemeral".to_string(),
+            ttl: None,
+        }
+    }
+
+    /// An ephemeral cache with a 1 hour TTL.
+    ///
+    /// Requests using it get the [`CacheControl::EXTENDED_TTL_BETA`] beta flag added
+    /// automatically.
+    pub fn ephemeral_1h() -> Self {
+        Self {
+            cache_type: "ephemeral".to_string(),
+            ttl: Some("1h".to_string()),
+        }
+    }
+
+    /// Returns `true` if this cache needs the extended TTL beta.
+    fn is_extended(&self) -> bool {
+        self.ttl.as_deref() == Some("1h")
+    }
+}
+
+/// Tool definition.
+///
+/// Tools allow Claude to perform actions outside its context, such as calculations or
+/// API calls. Custom tools are executed by you; server tools such as web search are
+/// executed by Anthropic and need no tool loop.
+///
+/// # Example
+///
+/// ```
+/// # use anthropic_api::messages::*;
+/// # use serde_json::json;
+/// let tools = vec![
+///     Tool::Custom {
+///         name: "get_weather".to_string(),
+///         description: "Get the current weather for a city".to_string(),
+///         input_schema: json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+///     },
+///     Tool::WebSearch {
+///         max_uses: Some(3),
+///         allowed_domains: None,
+///         blocked_domains: None,
+///         user_location: None,
+///     },
+/// ];
+/// assert_eq!(tools[1].name(), "web_search");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Tool {
+    /// A custom tool defined by a JSON schema and executed by the caller
+    Custom {
+        /// The name of the tool
+        name: String,
+        /// A description of what the tool does
+        description: String,
+        /// JSON Schema defining the input format for the tool
+        input_schema: Value,
+    },
+    /// The server-side web search tool (`web_search_20250305`)
+    WebSearch {
+        /// Maximum number of searches per request
+        max_uses: Option<u32>,
+        /// Only include results from these domains
+        allowed_domains: Option<Vec<String>>,
+        /// Never include results from these domains
+        blocked_domains: Option<Vec<String>>,
+        /// Approximate location of the user, used to localize results
+        user_location: Option<UserLocation>,
+    },
+    /// The computer use tool (`computer_20250124`)
+    ///
+    /// Requests using it automatically send the [`Tool::COMPUTER_USE_BETA`] header.
+    Computer {
+        /// Width of the display in pixels
+        display_width_px: u32,
+        /// Height of the display in pixels
+        display_height_px: u32,
+        /// X11 display number, if applicable
+        display_number: Option<u32>,
+    },
+    /// The text editor tool (`text_editor_20250124`)
+    ///
+    /// Requests using it automatically send the [`Tool::COMPUTER_USE_BETA`] header.
+    TextEditor,
+    /// The bash tool (`bash_20250124`)
+    ///
+    /// Requests using it automatically send the [`Tool::COMPUTER_USE_BETA`] header.
+    Bash,
+    /// The server-side code execution tool (`code_execution_20250522`)
+    ///
+    /// Requests using it automatically send the [`Tool::CODE_EXECUTION_BETA`] header.
+    CodeExecution,
+}
+
+impl Tool {
+    /// Beta header required by [`Tool::CodeExecution`].
+    pub const CODE_EXECUTION_BETA: &'static str = "code-execution-2025-05-22";
+
+    /// Beta header required by [`Tool::Computer`], [`Tool::TextEditor`], and [`Tool::Bash`].
+    pub const COMPUTER_USE_BETA: &'static str = "computer-use-2025-01-24";
+
+    /// Creates a custom tool, checking that its name is one the API accepts.
+    ///
+    /// Tool names must be 1 to 64 characters long and contain only ASCII letters, digits,
+    /// underscores, and hyphens. This is the recommended way to build a [`Tool::Custom`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// # use serde_json::json;
+    /// let tool = Tool::new("get_weather", "Get the weather", json!({"type": "object"}));
+    /// assert!(tool.is_ok());
+    ///
+    /// let tool = Tool::new("get weather!", "Get the weather", json!({"type": "object"}));
+    /// assert!(tool.is_err());
+    /// ```
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: Value,
+    ) -> Result<Self, InvalidToolName> {
+        let name = name.into();
+        let valid = (1..=64).contains(&name.len())
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-');
+        if !valid {
+            return Err(InvalidToolName { name });
+        }
+        Ok(Tool::Custom {
+            name,
+            description: description.into(),
+            input_schema,
+        })
+    }
+
+    /// Returns the name Claude uses to refer to the tool.
+    pub fn name(&self) -> &str {
+        match self {
+            Tool::Custom { name, .. } => name,
+            Tool::WebSearch { .. } => "web_search",
+            Tool::Computer { .. } => "computer",
+            Tool::TextEditor => "str_replace_editor",
+            Tool::Bash => "bash",
+            Tool::CodeExecution => "code_execution",
+        }
+    }
+
+    /// Returns the beta header a request using this tool must send, if any.
+    fn required_beta(&self) -> Option<&'static str> {
+        match self {
+            Tool::Custom { .. } | Tool::WebSearch { .. } => None,
+            Tool::Computer { .. } | Tool::TextEditor | Tool::Bash => Some(Self::COMPUTER_USE_BETA),
+            Tool::CodeExecution => Some(Self::CODE_EXECUTION_BETA),
+        }
+    }
+}
+
+/// A tool name that does not match `^[a-zA-Z0-9_-]{1,64}$`, returned by [`Tool::new`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidToolName {
+    /// The rejected name.
+    pub name: String,
+}
+
+impl std::fmt::Display for InvalidToolName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid tool name {:?}: names must be 1-64 characters of a-z, A-Z, 0-9, _ or -",
+            self.name
+        )
+    }
+}
+
+impl std::error::Error for InvalidToolName {}
+
+impl From<InvalidToolName> for AnthropicErrorResponse {
+    fn from(value: InvalidToolName) -> Self {
+        builder_error(value)
+    }
+}
+
+/// Wire format of [`Tool`]; server tools carry a versioned `type` and a fixed `name`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ToolRepr<'a> {
+    Custom {
+        name: &'a str,
+        description: &'a str,
+        input_schema: &'a Value,
+    },
+    WebSearch {
+        #[serde(rename = "type")]
+        tool_type: &'static str,
+        name: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_uses: &'a Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        allowed_domains: &'a Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        blocked_domains: &'a Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        user_location: &'a Option<UserLocation>,
+    },
+    Computer {
+        #[serde(rename = "type")]
+        tool_type: &'static str,
+        name: &'static str,
+        display_width_px: u32,
+        display_height_px: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        display_number: &'a Option<u32>,
+    },
+    Named {
+        #[serde(rename = "type")]
+        tool_type: &'static str,
+        name: &'static str,
+    },
+}
+
+impl Serialize for Tool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            Tool::Custom {
+                name,
+                description,
+                input_schema,
+            } => ToolRepr::Custom {
+                name,
+                description,
+                input_schema,
+            },
+            Tool::WebSearch {
+                max_uses,
+                allowed_domains,
+                blocked_domains,
+                user_location,
+            } => ToolRepr::WebSearch {
+                tool_type: "web_search_20250305",
+                name: "web_search",
+                max_uses,
+                allowed_domains,
+                blocked_domains,
+                user_location,
+            },
+            Tool::Computer {
+                display_width_px,
+                display_height_px,
+                display_number,
+            } => ToolRepr::Computer {
+                tool_type: "computer_20250124",
+                name: "computer",
+                display_width_px: *display_width_px,
+                display_height_px: *display_height_px,
+                display_number,
+            },
+            Tool::TextEditor => ToolRepr::Named {
+                tool_type: "text_editor_20250124",
+                name: "str_replace_editor",
+            },
+            Tool::Bash => ToolRepr::Named {
+                tool_type: "bash_20250124",
+                name: "bash",
+            },
+            Tool::CodeExecution => ToolRepr::Named {
+                tool_type: "code_execution_20250522",
+                name: "code_execution",
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+/// Approximate user location for localizing web search results.
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct UserLocation {
+    /// The type of location (always "approximate")
+    #[serde(rename = "type")]
+    pub location_type: String,
+    /// City name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    /// Region or state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Two-letter ISO country code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// IANA timezone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// A tool definition paired with the function that executes it.
+///
+/// Used with [`MessagesBuilder::run_with_tools`], which dispatches each tool use
+/// requested by Claude to the handler of the tool with the matching name.
+///
+/// # Example
+///
+/// ```
+/// # use anthropic_api::messages::*;
+/// # use serde_json::json;
+/// let calculator = ToolImpl::new(
+///     Tool::Custom {
+///         name: "add".to_string(),
+///         description: "Adds two numbers".to_string(),
+///         input_schema: json!({
+///             "type": "object",
+///             "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+///             "required": ["a", "b"]
+///         }),
+///     },
+///     |input| {
+///         let a = input["a"].as_f64().unwrap_or_default();
+///         let b = input["b"].as_f64().unwrap_or_default();
+///         Ok((a + b).to_string())
+///     },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ToolImpl {
+    /// The tool definition sent to the API
+    pub definition: Tool,
+    /// Runs the tool with the input chosen by Claude and returns the result text
+    pub handler: Arc<dyn Fn(Value) -> anyhow::Result<String> + Send + Sync>,
+}
+
+impl ToolImpl {
+    /// Creates a tool from its definition and handler.
+    pub fn new(
+        definition: Tool,
+        handler: impl Fn(Value) -> anyhow::Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            definition,
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolImpl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolImpl")
+            .field("definition", &self.definition)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Runs a tool asynchronously with the input chosen by Claude and returns the result text.
+type AsyncToolHandler =
+    Arc<dyn Fn(Value) -> BoxFuture<'static, anyhow::Result<String>> + Send + Sync>;
+
+/// Drives a conversation through tool use until Claude produces a final answer.
+///
+/// Each registered tool pairs a definition with an async handler. [`ToolRunner::run`] sends
+/// the request, dispatches every tool use to the handler of the tool with the matching name,
+/// feeds the results back as `tool_result` blocks, and repeats until the response stops for
+/// any reason other than tool use. Tool uses from the same turn are run concurrently.
+///
+/// The number of requests is capped by [`ToolRunner::max_iterations`] so a model that keeps
+/// calling tools cannot loop forever.
+///
+/// # Example
+///
+/// ```no_run
+/// # use anthropic_api::{messages::*, Credentials};
+/// # use serde_json::json;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let runner = ToolRunner::new()
+///     .tool(
+///         Tool::new(
+///             "get_weather",
+///             "Returns the current weather for a city",
+///             json!({
+///                 "type": "object",
+///                 "properties": {"city": {"type": "string"}},
+///                 "required": ["city"]
+///             }),
+///         )?,
+///         |input| async move {
+///             let city = input["city"].as_str().unwrap_or("unknown");
+///             Ok(format!("It is sunny in {city}"))
+///         },
+///     )
+///     .max_iterations(5);
+///
+/// let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+///     .credentials(Credentials::from_env())
+///     .user_text("What's the weather in Paris?");
+///
+/// let response = runner.run(builder).await?;
+/// println!("{}", response.text());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ToolRunner {
+    tools: Vec<(Tool, AsyncToolHandler)>,
+    max_iterations: usize,
+}
+
+impl ToolRunner {
+    /// Maximum number of requests sent by a runner unless configured otherwise.
+    pub const DEFAULT_MAX_ITERATIONS: usize = 25;
+
+    /// Creates a runner with no tools.
+    pub fn new() -> Self {
+        Self {
+            tools: Vec::new(),
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+        }
+    }
+
+    /// Registers a tool and the async handler that executes it.
+    ///
+    /// Registering a tool with the same name as an earlier one replaces it.
+    pub fn tool<F, Fut>(mut self, definition: Tool, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<String>> + Send + 'static,
+    {
+        self.tools
+            .retain(|(existing, _)| existing.name() != definition.name());
+        let handler: AsyncToolHandler = Arc::new(move |input| handler(input).boxed());
+        self.tools.push((definition, handler));
+        self
+    }
+
+    /// Registers a tool with a synchronous handler.
+    pub fn tool_impl(self, tool: ToolImpl) -> Self {
+        let handler = tool.handler;
+        self.tool(tool.definition, move |input| {
+            std::future::ready(handler(input))
+        })
+    }
+
+    /// Sets the maximum number of requests to send before giving up.
+    ///
+    /// Paused turns that are continued count towards the limit.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sends the request and runs the registered tools until Claude produces a final answer.
+    ///
+    /// The tool definitions are added to the request. Whenever Claude stops to use tools,
+    /// the assistant turn plus the tool results are appended to the conversation before
+    /// sending it again. Handler errors and unknown tool names are reported back to Claude
+    /// as error results. Paused turns are continued as with
+    /// [`MessagesBuilder::continue_from`]. Returns the first response that stops for any
+    /// other reason, or a `tool_runner` error once `max_iterations` requests have been sent
+    /// without one.
+    pub async fn run(&self, mut builder: MessagesBuilder) -> ApiResponseOrError<MessagesResponse> {
+        let mut definitions = builder.tools.take().flatten().unwrap_or_default();
+        definitions.retain(|existing| {
+            !self
+                .tools
+                .iter()
+                .any(|(definition, _)| definition.name() == existing.name())
+        });
+        definitions.extend(self.tools.iter().map(|(definition, _)| definition.clone()));
+        let builder = builder.tools(definitions);
+        let mut messages = builder.messages.clone().unwrap_or_default();
+
+        for _ in 0..self.max_iterations {
+            let response = builder.clone().messages(messages.clone()).create().await?;
+            if response.is_paused() {
+                messages.push(response.to_message());
+                continue;
+            }
+            if response.stop_reason != Some(StopReason::ToolUse) {
+                return Ok(response);
+            }
+
+            let results = join_all(response.tool_uses().into_iter().map(|tool_use| async move {
+                let handler = self
+                    .tools
+                    .iter()
+                    .find(|(definition, _)| definition.name() == tool_use.name)
+                    .map(|(_, handler)| handler);
+                let outcome = match handler {
+                    Some(handler) => handler(tool_use.input.clone()).await,
+                    None => Err(anyhow::anyhow!("Unknown tool: {}", tool_use.name)),
+                };
+                let (content, is_error) = match outcome {
+                    Ok(content) => (content, None),
+                    Err(e) => (e.to_string(), Some(true)),
+                };
+                RequestContentBlock::ToolResult {
+                    tool_use_id: tool_use.id.to_string(),
+                    content,
+                    is_error,
+                }
+            }))
+            .await;
+
+            messages.push(response.to_message());
+            messages.push(Message {
+                role: MessageRole::User,
+                content: MessageContent::ContentBlocks(results),
+            });
+        }
+
+        Err(AnthropicErrorResponse::new(
+            format!(
+                "Tool use did not finish within {} iterations",
+                self.max_iterations
+            ),
+            "tool_runner".to_string(),
+        ))
+    }
+}
+
+impl Default for ToolRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ToolRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolRunner")
+            .field(
+                "tools",
+                &self
+                    .tools
+                    .iter()
+                    .map(|(definition, _)| definition.name())
+                    .collect::<Vec<_>>(),
+            )
+            .field("max_iterations", &self.max_iterations)
+            .finish()
+    }
+}
+
+/// Tool choice specification.
+///
+/// Controls how Claude decides whether to use tools.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum ToolChoice {
+    /// Claude decides whether to use tools
+    #[serde(rename = "auto")]
+    Auto,
+    /// Claude can use any available tool
+    #[serde(rename = "any")]
+    Any,
+    /// Claude must use the specified tool
+    #[serde(rename = "tool")]
+    Tool { name: String },
+    /// Claude must not use any tools
+    ///
+    /// The `tools` are still sent, so a conversation can keep the same tool set and force
+    /// a text-only reply for a single turn.
+    #[serde(rename = "none")]
+    None,
+}
+
+/// Service tier for a request.
+///
+/// Controls whether the request may use Priority Tier capacity.
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceTier {
+    /// Use Priority Tier capacity if available, falling back to standard
+    Auto,
+    /// Only use standard capacity
+    StandardOnly,
 }
 
 /// Metadata for the request.
 ///
 /// Additional information about the request that isn't
 /// directly related to generation behavior.
-#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
 pub struct Metadata {
     /// Optional user identifier for tracking purposes
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub user_id: Option<String>,
 }
 
+impl Metadata {
+    /// Creates metadata identifying the end user the request is made on behalf of.
+    ///
+    /// Use an opaque value such as a hash or UUID; don't send names, emails, or other
+    /// identifying information.
+    pub fn user(user_id: impl Into<String>) -> Self {
+        Self {
+            user_id: Some(user_id.into()),
+        }
+    }
+}
+
 // Implementation for non-streaming response
 impl MessagesResponse {
     /// Creates a new message request and returns the response.
     ///
     /// This method sends a request to the Messages API and returns
-    /// the complete response.
+    /// the complete response. The request's `stream` flag is cleared, since a
+    /// streamed body can't be read as a single response; use
+    /// [`StreamEvent::create_stream`] to stream.
+    ///
+    /// The request runs entirely inside the returned future, including any retries, so
+    /// dropping the future cancels it: the connection is closed rather than returned to the
+    /// pool, and nothing keeps running in the background. With the `cancellation` feature,
+    /// `MessagesResponse::create_with_cancellation` ties this to a `CancellationToken`.
     ///
     /// # Example
     ///
@@ -384,10 +1922,7 @@ impl MessagesResponse {
     /// let credentials = Credentials::from_env();
     /// let request = MessagesRequest {
     ///     model: "claude-3-7-sonnet-20250219".to_string(),
-    ///     messages: vec![Message {
-    ///         role: MessageRole::User,
-    ///         content: MessageContent::Text("Hello!".to_string()),
-    ///     }],
+    ///     messages: vec![Message::user("Hello!")],
     ///     max_tokens: 100,
     ///     credentials: Some(credentials),
     ///     metadata: None,
@@ -400,112 +1935,139 @@ impl MessagesResponse {
     ///     tools: None,
     ///     top_k: None,
     ///     top_p: None,
+    ///     service_tier: None,
+    ///     container: None,
+    ///     betas: None,
+    ///     stream_idle_timeout: None,
+    ///     base_url: None,
     /// };
     ///
     /// let response = MessagesResponse::create(request).await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn create(request: MessagesRequest) -> ApiResponseOrError<Self> {
-        let credentials_opt = request.credentials.clone();
-        anthropic_post("messages", &request, credentials_opt).await
+    pub async fn create(mut request: MessagesRequest) -> ApiResponseOrError<Self> {
+        request.stream = None;
+        request.validate()?;
+        let credentials = request.effective_credentials()?;
+        let betas = request.effective_betas();
+        #[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+        let started = std::time::Instant::now();
+        let (mut response, headers): (Self, _) =
+            anthropic_post("messages", &request, &betas, Some(credentials)).await?;
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        response.request_id = header("request-id");
+        response.organization_id = header("anthropic-organization-id");
+        #[cfg(feature = "tracing")]
+        log_served_model(&request.model, &response);
+        #[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+        log_completion(&response, started.elapsed());
+        #[cfg(all(feature = "tracing", target_arch = "wasm32"))]
+        log_completion(&response, Duration::ZERO);
+        Ok(response)
     }
-}
 
-// Implementation for streaming response
-impl StreamEvent {
-    /// Creates a new streaming message request and returns a channel of events.
+    /// Sends a message like [`MessagesResponse::create`], blocking the current thread until
+    /// the response arrives.
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(request: MessagesRequest) -> ApiResponseOrError<Self> {
+        block_on(Self::create(request))
+    }
+
+    /// Sends a message like [`MessagesResponse::create`], abandoning the request as soon as
+    /// `token` is cancelled.
     ///
-    /// This method sends a request to the Messages API in streaming mode
-    /// and returns a channel that will receive the streaming events.
+    /// On cancellation this returns a `cancelled` error, and by the time it does, the
+    /// in-flight request has been dropped and its connection closed. A token that is already
+    /// cancelled returns the error without sending anything.
     ///
     /// # Example
     ///
     /// ```no_run
     /// # use anthropic_api::{messages::*, Credentials};
+    /// # use tokio_util::sync::CancellationToken;
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let credentials = Credentials::from_env();
-    /// let mut request = MessagesRequest {
-    ///     model: "claude-3-7-sonnet-20250219".to_string(),
-    ///     messages: vec![Message {
-    ///         role: MessageRole::User,
-    ///         content: MessageContent::Text("Hello!".to_string()),
-    ///     }],
-    ///     max_tokens: 100,
-    ///     credentials: Some(credentials),
-    ///     metadata: None,
-    ///     stop_sequences: None,
-    ///     stream: Some(true),
-    ///     system: None,
-    ///     temperature: None,
-    ///     thinking: None,
-    ///     tool_choice: None,
-    ///     tools: None,
-    ///     top_k: None,
-    ///     top_p: None,
-    /// };
+    /// let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .build()?;
     ///
-    /// let mut stream = StreamEvent::create_stream(request).await?;
+    /// let token = CancellationToken::new();
+    /// let on_navigate = token.clone();
+    /// // Elsewhere, when the user navigates away: on_navigate.cancel();
     ///
-    /// while let Some(event) = stream.recv().await {
-    ///     // Process streaming events
-    ///     println!("{:?}", event);
+    /// match MessagesResponse::create_with_cancellation(request, &token).await {
+    ///     Ok(response) => println!("{}", response),
+    ///     Err(e) if e.error.error_type == "cancelled" => println!("Cancelled"),
+    ///     Err(e) => return Err(e.into()),
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn create_stream(
+    #[cfg(feature = "cancellation")]
+    pub async fn create_with_cancellation(
         request: MessagesRequest,
-    ) -> Result<Receiver<Self>, CannotCloneRequestError> {
-        let credentials_opt = request.credentials.clone();
-        let stream = anthropic_request_stream(
-            Method::POST,
-            "messages",
-            |r| r.json(&request),
-            credentials_opt,
-        )
-        .await?;
-        let (tx, rx) = channel::<Self>(32);
-        tokio::spawn(forward_deserialized_anthropic_stream(stream, tx));
-        Ok(rx)
+        token: &CancellationToken,
+    ) -> ApiResponseOrError<Self> {
+        let cancelled = || {
+            AnthropicErrorResponse::new(
+                "Request was cancelled".to_string(),
+                "cancelled".to_string(),
+            )
+        };
+        if token.is_cancelled() {
+            return Err(cancelled());
+        }
+        let request = std::pin::pin!(Self::create(request));
+        let cancellation = std::pin::pin!(token.cancelled());
+        match future::select(cancellation, request).await {
+            Either::Left(_) => Err(cancelled()),
+            Either::Right((result, _)) => result,
+        }
     }
-}
 
-/// Processes the event stream and forwards events to the channel.
-///
-/// This internal function handles the raw event stream from the API
-/// and deserializes events into the `StreamEvent` enum.
-async fn forward_deserialized_anthropic_stream(
-    mut stream: EventSource,
-    tx: Sender<StreamEvent>,
-) -> anyhow::Result<()> {
-    while let Some(event) = stream.next().await {
-        let event = event?;
-        if let Event::Message(event) = event {
-            let stream_event = serde_json::from_str::<StreamEvent>(&event.data)?;
-            if matches!(stream_event, StreamEvent::Ping) {
-                continue; // Ignore ping events
-            }
-            tx.send(stream_event).await?;
-        }
+    /// Returns the text of all `Text` blocks, concatenated in order.
+    ///
+    /// Tool use and thinking blocks are skipped.
+    pub fn text(&self) -> String {
+        self.text_blocks().concat()
     }
-    Ok(())
-}
 
-// Builder convenience methods
-impl MessagesBuilder {
-    pub fn builder(model: &str, messages: impl Into<Vec<Message>>, max_tokens: u64) -> Self {
-        Self::create_empty()
-            .model(model)
-            .messages(messages)
-            .max_tokens(max_tokens)
+    /// Returns the text of each `Text` block, in order.
+    pub fn text_blocks(&self) -> Vec<&str> {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ResponseContentBlock::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
     }
 
-    /// Creates a new message request and returns the response.
-    ///
-    /// This is a convenience method that builds the request from the builder
-    /// and sends it to the Messages API.
+    /// Returns the tool use requests in the response, in order.
+    pub fn tool_uses(&self) -> Vec<ToolUse<'_>> {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ResponseContentBlock::ToolUse { id, name, input } => {
+                    Some(ToolUse { id, name, input })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the model that generated the response.
+    ///
+    /// When the request named an alias such as `claude-3-7-sonnet-latest`, this is the dated
+    /// model it resolved to. [`MessagesResponse::create`] logs a warning if a dated model
+    /// was requested but a different one served the response.
     ///
     /// # Example
     ///
@@ -513,25 +2075,227 @@ impl MessagesBuilder {
     /// # use anthropic_api::{messages::*, Credentials};
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let credentials = Credentials::from_env();
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-latest", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .create()
+    ///     .await?;
+    /// assert_eq!(response.served_model(), "claude-3-7-sonnet-20250219");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn served_model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the stop sequence that ended generation, or `None` if generation stopped
+    /// for any other reason.
+    pub fn stopped_on_sequence(&self) -> Option<&str> {
+        match self.stop_reason {
+            Some(StopReason::StopSequence) => self.stop_sequence.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the API paused a long-running turn, such as one using server
+    /// tools, and expects it to be continued with [`MessagesBuilder::continue_from`].
+    pub fn is_paused(&self) -> bool {
+        self.stop_reason == Some(StopReason::PauseTurn)
+    }
+
+    /// Returns `true` if the model declined to respond, for example for safety reasons.
     ///
-    /// let response =MessagesBuilder::builder("claude-3-7-sonnet-20250219",[], 1024)
-    ///     .credentials(credentials.clone())
+    /// Any content is partial and should not be shown as a complete answer. Sending the same
+    /// request again is unlikely to produce a different outcome, so a refusal is better
+    /// surfaced to the user than retried.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
     ///     .create()
-    ///     .await
-    ///     .unwrap();
+    ///     .await?;
+    ///
+    /// if response.is_refusal() {
+    ///     println!("Sorry, that request can't be answered.");
+    /// } else {
+    ///     println!("{}", response.text());
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn create(self) -> ApiResponseOrError<MessagesResponse> {
-        let request = self.build().unwrap();
-        MessagesResponse::create(request).await
+    pub fn is_refusal(&self) -> bool {
+        self.stop_reason == Some(StopReason::Refusal)
+    }
+
+    /// Returns `true` if generation stopped because the model's context window was full.
+    ///
+    /// The response is truncated, and continuing on the same model cannot add more output, so
+    /// this is the signal to retry with a larger-context model or a shorter prompt. Hitting
+    /// the request's own `max_tokens` budget is reported as [`StopReason::MaxTokens`] instead.
+    /// A prompt that is too long to start with is rejected with an `invalid_request_error`
+    /// rather than a stop reason.
+    pub fn hit_context_limit(&self) -> bool {
+        self.stop_reason == Some(StopReason::ModelContextWindowExceeded)
+    }
+
+    /// Converts the response into the assistant [`Message`] to append to the conversation
+    /// history for the next turn.
+    ///
+    /// Every content block is carried over, including tool use blocks, which the matching
+    /// tool results refer to, and thinking blocks with their signatures, which the API
+    /// verifies when continuing a thinking conversation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut messages = vec![Message::user(MessageContent::Text("Hello!".to_string()))];
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", messages.clone(), 1024)
+    ///     .credentials(Credentials::from_env())
+    ///     .create()
+    ///     .await?;
+    /// messages.push(response.to_message());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_message(&self) -> Message {
+        Message {
+            role: MessageRole::Assistant,
+            content: MessageContent::ContentBlocks(
+                self.content.iter().cloned().map(Into::into).collect(),
+            ),
+        }
+    }
+}
+
+/// Writes the response's text, as returned by [`MessagesResponse::text`].
+impl std::fmt::Display for MessagesResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.text_blocks()
+            .into_iter()
+            .try_for_each(|text| f.write_str(text))
+    }
+}
+
+/// Logs the model that served a request when it differs from the requested one.
+///
+/// Resolving an alias is logged at `debug`; a dated model ID answered by a different model
+/// is logged at `warn`, since that is a silent substitution.
+#[cfg(feature = "tracing")]
+fn log_served_model(requested: &str, response: &MessagesResponse) {
+    let served = response.served_model();
+    if served == requested {
+        return;
+    }
+    // Only a dated model ID pins a version; aliases are expected to resolve to one.
+    let pinned = requested
+        .rsplit('-')
+        .next()
+        .is_some_and(|suffix| suffix.len() == 8 && suffix.bytes().all(|b| b.is_ascii_digit()));
+    if pinned {
+        tracing::warn!(
+            requested = %requested,
+            served = %served,
+            request_id = response.request_id.as_deref(),
+            "Response was served by a different model than requested"
+        );
+    } else {
+        tracing::debug!(requested = %requested, served = %served, "Model alias resolved");
+    }
+}
+
+/// Emits an `info` event summarizing a completed message request as structured fields.
+///
+/// `duration_ms` covers the whole call, including retries, and is always zero on wasm32.
+#[cfg(feature = "tracing")]
+fn log_completion(response: &MessagesResponse, duration: Duration) {
+    tracing::info!(
+        model = %response.model,
+        input_tokens = response.usage.input_tokens,
+        output_tokens = response.usage.output_tokens,
+        cache_creation_input_tokens = response.usage.cache_creation_input_tokens,
+        cache_read_input_tokens = response.usage.cache_read_input_tokens,
+        stop_reason = ?response.stop_reason,
+        request_id = response.request_id.as_deref(),
+        duration_ms = duration.as_millis() as u64,
+        "Message request completed"
+    );
+}
+
+/// A borrowed view of a `tool_use` content block, returned by [`MessagesResponse::tool_uses`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ToolUse<'a> {
+    /// The id to reference in the matching `tool_result` block
+    pub id: &'a str,
+    /// The name of the tool to call
+    pub name: &'a str,
+    /// The input to the tool
+    pub input: &'a Value,
+}
+
+impl ToolUse<'_> {
+    /// Deserializes the tool input into `T`.
+    ///
+    /// Returns a `json_parse_error` if the input does not match the shape of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # use serde::Deserialize;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #[derive(Deserialize)]
+    /// struct CalcArgs {
+    ///     operation: String,
+    ///     operands: Vec<f64>,
+    /// }
+    ///
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .credentials(Credentials::from_env())
+    ///     .user_text("What is 15 + 27?")
+    ///     .create()
+    ///     .await?;
+    ///
+    /// for tool_use in response.tool_uses() {
+    ///     let args = tool_use.parse_input::<CalcArgs>()?;
+    ///     println!("{} {:?}", args.operation, args.operands);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_input<T: DeserializeOwned>(&self) -> ApiResponseOrError<T> {
+        T::deserialize(self.input).map_err(|e| {
+            AnthropicErrorResponse::new(
+                format!("Failed to parse input for tool {}: {}", self.name, e),
+                "json_parse_error".to_string(),
+            )
+        })
     }
+}
 
+// Implementation for streaming response
+impl StreamEvent {
     /// Creates a new streaming message request and returns a channel of events.
     ///
-    /// This is a convenience method that builds the request from the builder
-    /// and sends it to the Messages API in streaming mode.
+    /// This method sends a request to the Messages API in streaming mode
+    /// and returns a channel that will receive the streaming events. The request is
+    /// always sent with `stream` set to `true`, whatever the field holds.
+    ///
+    /// If the stream fails (a transport error, an HTTP error status, or an event that
+    /// cannot be parsed), the error is sent as the final item before the channel closes,
+    /// so a stream that simply ends was delivered in full.
+    ///
+    /// Dropping the receiver cancels the stream: the connection is closed without waiting
+    /// for the next event.
     ///
     /// # Example
     ///
@@ -540,117 +2304,3227 @@ impl MessagesBuilder {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let credentials = Credentials::from_env();
+    /// let mut request = MessagesRequest {
+    ///     model: "claude-3-7-sonnet-20250219".to_string(),
+    ///     messages: vec![Message::user("Hello!")],
+    ///     max_tokens: 100,
+    ///     credentials: Some(credentials),
+    ///     metadata: None,
+    ///     stop_sequences: None,
+    ///     stream: None,
+    ///     system: None,
+    ///     temperature: None,
+    ///     thinking: None,
+    ///     tool_choice: None,
+    ///     tools: None,
+    ///     top_k: None,
+    ///     top_p: None,
+    ///     service_tier: None,
+    ///     container: None,
+    ///     betas: None,
+    ///     stream_idle_timeout: None,
+    ///     base_url: None,
+    /// };
     ///
-    /// let mut stream =MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
-    ///     .credentials(credentials)
-    ///     .create_stream()
-    ///     .await?;
+    /// let mut stream = StreamEvent::create_stream(request).await?;
     ///
     /// while let Some(event) = stream.recv().await {
     ///     // Process streaming events
-    ///     println!("{:?}", event);
+    ///     println!("{:?}", event?);
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn create_stream(self) -> Result<Receiver<StreamEvent>, CannotCloneRequestError> {
-        let mut request = self.build().expect("Failed to build MessagesRequest");
-        request.stream = Some(true);
-        StreamEvent::create_stream(request).await
+    #[cfg(feature = "runtime")]
+    pub async fn create_stream(
+        request: MessagesRequest,
+    ) -> ApiResponseOrError<Receiver<ApiResponseOrError<Self>>> {
+        let idle_timeout = request.stream_idle_timeout;
+        let stream = match open_event_source(request).await {
+            Ok(stream) => stream,
+            Err(e) => return Ok(error_channel(e)),
+        };
+        let (tx, rx) = channel::<ApiResponseOrError<Self>>(32);
+        tokio::spawn(forward_deserialized_anthropic_stream(
+            stream,
+            idle_timeout,
+            tx,
+        ));
+        Ok(rx)
     }
-}
 
-// Helper to create a builder with required fields
-impl MessagesResponse {
-    /// Creates a new builder with the required fields.
+    /// Creates a new streaming message request and returns a [`Stream`] of events.
     ///
-    /// This is a convenience method to create a builder with the
-    /// minimum required fields for a message request.
+    /// Unlike [`StreamEvent::create_stream`], no task is spawned: events are read from the
+    /// connection as the stream is polled, so this works without a multithreaded runtime,
+    /// including on wasm32. Errors are yielded as the final item of the stream.
     ///
     /// # Example
     ///
     /// ```no_run
     /// # use anthropic_api::{messages::*, Credentials};
+    /// # use futures_util::StreamExt;
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let credentials = Credentials::from_env();
+    /// let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .build()?;
     ///
-    /// let response =MessagesBuilder::builder(
-    ///     "claude-3-7-sonnet-20250219",
-    ///     vec![Message {
-    ///         role: MessageRole::User,
-    ///         content: MessageContent::Text("Hello!".to_string()),
-    ///     }],
-    ///     100,
-    /// )
-    /// .credentials(credentials)
-    /// .create()
-    /// .await?;
+    /// let stream = StreamEvent::create_event_stream(request).await?;
+    /// let mut stream = std::pin::pin!(stream);
+    /// while let Some(event) = stream.next().await {
+    ///     println!("{:?}", event?);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn builder(
-        model: &str,
-        messages: impl Into<Vec<Message>>,
-        max_tokens: u64,
-    ) -> MessagesBuilder {
-        MessagesBuilder::create_empty()
-            .model(model)
-            .messages(messages)
-            .max_tokens(max_tokens)
+    pub async fn create_event_stream(
+        request: MessagesRequest,
+    ) -> ApiResponseOrError<impl Stream<Item = ApiResponseOrError<Self>>> {
+        open_event_stream(Ok(request)).await
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Sends a streaming request, turning request errors into the single item of the stream.
+async fn open_event_stream(
+    request: ApiResponseOrError<MessagesRequest>,
+) -> ApiResponseOrError<impl Stream<Item = ApiResponseOrError<StreamEvent>>> {
+    let (stream, idle_timeout) = match request {
+        Ok(request) => {
+            let idle_timeout = request.stream_idle_timeout;
+            (open_event_source(request).await, idle_timeout)
+        }
+        Err(e) => (Err(e), None),
+    };
+    Ok(deserialized_anthropic_stream(stream, idle_timeout))
+}
 
-    #[tokio::test]
-    async fn test_simple_message() {
-        let credentials = Credentials::from_env();
+/// Opens the event source for a streaming request.
+///
+/// This is the single entry point for streaming requests, so `stream` is always set to
+/// `true` here regardless of what the caller passed. Callers deliver any error as the only
+/// item of the stream.
+async fn open_event_source(mut request: MessagesRequest) -> ApiResponseOrError<EventSource> {
+    request.stream = Some(true);
+    request.validate()?;
+    let credentials = request.effective_credentials()?;
+    let betas = request.effective_betas();
+    anthropic_request_stream(
+        Method::POST,
+        "messages",
+        |r| with_betas(r.json(&request), &betas),
+        credentials,
+    )
+    .await
+}
 
-        let response = MessagesResponse::builder(
-            "claude-3-7-sonnet-20250219",
-            vec![Message {
-                role: MessageRole::User,
-                content: MessageContent::Text("Hello!".to_string()),
-            }],
-            100,
-        )
-        .credentials(credentials)
-        .create()
-        .await
-        .unwrap();
+/// Deserializes the raw event stream from the API into [`StreamEvent`]s.
+///
+/// Ping events are skipped. An error (including `source` itself being an error) is yielded
+/// as the final item, and the event source is closed once the stream finishes. The stream
+/// ends cleanly at `message_stop` or an `error` event, so a connection torn down after the
+/// final event is not reported as an error. If the connection ends before either arrives,
+/// or `idle_timeout` elapses without any event arriving, a `stream` error ends the stream.
+fn deserialized_anthropic_stream(
+    source: ApiResponseOrError<EventSource>,
+    idle_timeout: Option<Duration>,
+) -> impl Stream<Item = ApiResponseOrError<StreamEvent>> {
+    futures_util::stream::unfold(Some(source), move |state| async move {
+        let mut stream = match state? {
+            Ok(stream) => stream,
+            Err(error) => return Some((Err(error), None)),
+        };
+        let result = loop {
+            let event = match next_event(&mut stream, idle_timeout).await {
+                Ok(event) => event,
+                Err(elapsed) => {
+                    break Err(AnthropicErrorResponse::new(
+                        format!("No stream events received for {:?}", elapsed),
+                        "stream".to_string(),
+                    ))
+                }
+            };
+            match event {
+                Some(Ok(Event::Open)) => continue,
+                Some(Ok(Event::Message(message))) => {
+                    match serde_json::from_str::<StreamEvent>(&message.data) {
+                        Ok(StreamEvent::Ping) => continue, // Ignore ping events
+                        Ok(stream_event) => break Ok(stream_event),
+                        Err(e) => {
+                            break Err(AnthropicErrorResponse::new(
+                                format!("Failed to parse stream event: {}", e),
+                                "json_parse_error".to_string(),
+                            ))
+                        }
+                    }
+                }
+                // The stream is only complete once `message_stop` has arrived, which ends it
+                // below, so the connection closing here means the message was cut short.
+                None | Some(Err(reqwest_eventsource::Error::StreamEnded)) => {
+                    break Err(AnthropicErrorResponse::new(
+                        "Stream ended before message_stop".to_string(),
+                        "stream".to_string(),
+                    ))
+                }
+                Some(Err(e)) => break Err(stream_error(e).await),
+            }
+        };
 
-        assert!(!response.content.is_empty());
+        if matches!(
+            result,
+            Err(_) | Ok(StreamEvent::MessageStop | StreamEvent::Error { .. })
+        ) {
+            // Stop the event source from reconnecting, and ignore whatever the connection
+            // does after the final event.
+            stream.close();
+            return Some((result, None));
+        }
+        Some((result, Some(Ok(stream))))
+    })
+}
+
+/// Waits for the next raw event, or returns the timeout if `idle_timeout` elapses first.
+///
+/// Timeouts need a timer, so without the `runtime` feature this waits indefinitely.
+async fn next_event(
+    stream: &mut EventSource,
+    idle_timeout: Option<Duration>,
+) -> Result<Option<Result<Event, reqwest_eventsource::Error>>, Duration> {
+    #[cfg(feature = "runtime")]
+    if let Some(idle_timeout) = idle_timeout {
+        return tokio::time::timeout(idle_timeout, stream.next())
+            .await
+            .map_err(|_| idle_timeout);
     }
+    #[cfg(not(feature = "runtime"))]
+    let _ = idle_timeout;
+    Ok(stream.next().await)
+}
 
-    #[tokio::test]
-    async fn test_streaming_message() {
-        let credentials = Credentials::from_env();
+/// Processes the event stream and forwards events to the channel.
+///
+/// Errors are forwarded on the channel and end the stream. Dropping the receiver closes the
+/// connection right away, even while waiting for the next event, so abandoning a stream
+/// stops the generation and frees the socket.
+#[cfg(feature = "runtime")]
+async fn forward_deserialized_anthropic_stream(
+    stream: EventSource,
+    idle_timeout: Option<Duration>,
+    tx: Sender<ApiResponseOrError<StreamEvent>>,
+) {
+    let events = deserialized_anthropic_stream(Ok(stream), idle_timeout);
+    let mut events = std::pin::pin!(events);
+    loop {
+        let closed = std::pin::pin!(tx.closed());
+        let result = match future::select(closed, events.next()).await {
+            // The receiver was dropped; returning drops the event source and its connection.
+            Either::Left(_) => return,
+            Either::Right((Some(result), _)) => result,
+            Either::Right((None, _)) => return,
+        };
+        if tx.send(result).await.is_err() {
+            return;
+        }
+    }
+}
 
-        let mut stream = MessagesResponse::builder(
-            "claude-3-7-sonnet-20250219",
-            vec![Message {
-                role: MessageRole::User,
-                content: MessageContent::Text("Hello!".to_string()),
-            }],
-            100,
-        )
-        .credentials(credentials)
-        .create_stream()
-        .await
-        .unwrap();
+/// Converts an event source error into an error response.
+///
+/// For HTTP error statuses the body is the API's usual JSON error, so it is
+/// returned as-is when it can be parsed.
+async fn stream_error(error: reqwest_eventsource::Error) -> AnthropicErrorResponse {
+    if let reqwest_eventsource::Error::InvalidStatusCode(status, response) = error {
+        return match response.json::<AnthropicErrorResponse>().await {
+            Ok(error) => error,
+            Err(_) => AnthropicErrorResponse::new(
+                format!("Stream request failed with status {}", status),
+                "http".to_string(),
+            ),
+        };
+    }
+    AnthropicErrorResponse::new(error.to_string(), "stream".to_string())
+}
 
-        while let Some(event) = stream.recv().await {
-            match event {
-                StreamEvent::ContentBlockDelta { delta, .. } => {
-                    if let ContentBlockDelta::Text { text } = delta {
-                        print!("{}", text);
+/// Accumulates streaming events into a complete response.
+///
+/// Feed every event received from a stream into [`StreamAccumulator::push`], then
+/// call [`StreamAccumulator::into_response`] or [`StreamAccumulator::into_message`]
+/// once the stream has finished.
+///
+/// # Example
+///
+/// ```no_run
+/// # use anthropic_api::{messages::*, Credentials};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let credentials = Credentials::from_env();
+/// let mut messages = vec![Message::user("Hello!")];
+///
+/// let mut stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", messages.clone(), 1024)
+///     .credentials(credentials)
+///     .create_stream()
+///     .await?;
+///
+/// let mut accumulator = StreamAccumulator::new();
+/// while let Some(event) = stream.recv().await {
+///     accumulator.push(&event?);
+/// }
+///
+/// // Append the assistant turn to the conversation history
+/// messages.push(accumulator.into_message()?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StreamAccumulator {
+    message: Option<MessageStart>,
+    content: Vec<ResponseContentBlock>,
+    partial_json: HashMap<usize, String>,
+    stop_reason: Option<StopReason>,
+    stop_sequence: Option<String>,
+    usage: Usage,
+    container: Option<Container>,
+}
+
+impl StreamAccumulator {
+    /// Creates an empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a single streaming event to the accumulated state.
+    pub fn push(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::MessageStart { message } => {
+                self.content = message
+                    .content
+                    .iter()
+                    .cloned()
+                    .map(ResponseContentBlock::from)
+                    .collect();
+                self.usage = message.usage.clone();
+                self.message = Some(message.clone());
+            }
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                let index = *index as usize;
+                let block = ResponseContentBlock::from(content_block.clone());
+                if index < self.content.len() {
+                    self.content[index] = block;
+                } else {
+                    self.content.push(block);
+                }
+            }
+            StreamEvent::ContentBlockDelta { index, delta } => {
+                let index = *index as usize;
+                match (self.content.get_mut(index), delta) {
+                    (
+                        Some(ResponseContentBlock::Text { text, .. }),
+                        ContentBlockDelta::Text { text: delta },
+                    ) => {
+                        text.push_str(delta);
+                    }
+                    (
+                        Some(ResponseContentBlock::Text { citations, .. }),
+                        ContentBlockDelta::CitationsDelta { citation },
+                    ) => {
+                        citations
+                            .get_or_insert_with(Vec::new)
+                            .push(citation.clone());
+                    }
+                    (
+                        Some(
+                            ResponseContentBlock::ToolUse { .. }
+                            | ResponseContentBlock::ServerToolUse { .. },
+                        ),
+                        ContentBlockDelta::InputJsonDelta { partial_json },
+                    ) => {
+                        self.partial_json
+                            .entry(index)
+                            .or_default()
+                            .push_str(partial_json);
                     }
+                    (
+                        Some(ResponseContentBlock::Thinking { thinking, .. }),
+                        ContentBlockDelta::ThinkingDelta { thinking: delta },
+                    ) => {
+                        thinking.push_str(delta);
+                    }
+                    (
+                        Some(ResponseContentBlock::Thinking { signature, .. }),
+                        ContentBlockDelta::SignatureDelta { signature: delta },
+                    ) => {
+                        signature.push_str(delta);
+                    }
+                    _ => {}
+                }
+            }
+            StreamEvent::MessageDelta { delta, usage } => {
+                self.stop_reason = delta.stop_reason.clone();
+                self.stop_sequence = delta.stop_sequence.clone();
+                if delta.container.is_some() {
+                    self.container = delta.container.clone();
                 }
-                _ => {}
+                self.usage.merge(usage);
+            }
+            StreamEvent::ContentBlockStop { .. }
+            | StreamEvent::MessageStop
+            | StreamEvent::Ping
+            | StreamEvent::Error { .. }
+            | StreamEvent::Unknown => {}
+        }
+    }
+
+    /// Returns the token usage accumulated so far.
+    ///
+    /// Input token counts from `message_start` are merged with the output token counts
+    /// from `message_delta`, so once the stream has finished this is the complete usage.
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+
+    /// Returns the tool name and parsed input of the tool use block at `index`.
+    ///
+    /// The input is only complete once the block's `content_block_stop` event has been pushed.
+    fn tool_input(&self, index: usize) -> Option<(&str, ApiResponseOrError<Value>)> {
+        match self.content.get(index)? {
+            ResponseContentBlock::ToolUse { id, name, input } => {
+                let input = match self.partial_json.get(&index) {
+                    Some(json) => parse_tool_input(id, name, input, json),
+                    None => Ok(input.clone()),
+                };
+                Some((name, input))
+            }
+            _ => None,
+        }
+    }
+
+    /// Builds the complete response from the accumulated events.
+    ///
+    /// Returns an error if no `message_start` event was seen or if a tool use
+    /// block's streamed input is not valid JSON, for example because the stream was cut off
+    /// mid-block. The error names the tool and includes the accumulated input text, and no
+    /// partially parsed input is ever returned.
+    pub fn into_response(self) -> ApiResponseOrError<MessagesResponse> {
+        let Some(message) = self.message else {
+            return Err(AnthropicErrorResponse::new(
+                "Stream ended before a message_start event was received".to_string(),
+                "stream".to_string(),
+            ));
+        };
+
+        let mut content = self.content;
+        let mut partial_json: Vec<_> = self.partial_json.into_iter().collect();
+        partial_json.sort_unstable_by_key(|(index, _)| *index);
+        for (index, json) in partial_json {
+            if let Some(
+                ResponseContentBlock::ToolUse { id, name, input }
+                | ResponseContentBlock::ServerToolUse { id, name, input },
+            ) = content.get_mut(index)
+            {
+                *input = parse_tool_input(id, name, input, &json)?;
+            }
+        }
+
+        Ok(MessagesResponse {
+            id: message.id,
+            model: message.model,
+            role: message.role,
+            content,
+            stop_reason: self.stop_reason,
+            stop_sequence: self.stop_sequence,
+            typ: MessagesResponse::TYPE.to_string(),
+            usage: self.usage,
+            container: self.container,
+            logprobs: None,
+            request_id: None,
+            organization_id: None,
+        })
+    }
+
+    /// Builds the assistant [`Message`] from the accumulated events, ready to be
+    /// appended to the conversation history.
+    ///
+    /// All content blocks are carried over, including thinking blocks and their
+    /// signatures, which the API requires when continuing a thinking conversation.
+    pub fn into_message(self) -> ApiResponseOrError<Message> {
+        Ok(self.into_response()?.to_message())
+    }
+}
+
+/// Parses the concatenated `input_json_delta` fragments of a tool use block.
+///
+/// A tool called without arguments may stream no input at all, in which case the input from
+/// the `content_block_start` event is kept. Malformed or truncated input is a
+/// `json_parse_error` naming the tool and including the accumulated text.
+fn parse_tool_input(id: &str, name: &str, start: &Value, json: &str) -> ApiResponseOrError<Value> {
+    if json.trim().is_empty() {
+        return Ok(start.clone());
+    }
+    serde_json::from_str(json).map_err(|e| {
+        AnthropicErrorResponse::new(
+            format!(
+                "Failed to parse streamed input of tool {} ({}): {}; accumulated input: {}",
+                name, id, e, json
+            ),
+            "json_parse_error".to_string(),
+        )
+    })
+}
+
+impl From<ContentBlockStart> for ResponseContentBlock {
+    fn from(block: ContentBlockStart) -> Self {
+        match block {
+            ContentBlockStart::Text { text } => ResponseContentBlock::Text {
+                text,
+                citations: None,
+            },
+            ContentBlockStart::ToolUse { id, name, input } => {
+                ResponseContentBlock::ToolUse { id, name, input }
             }
+            ContentBlockStart::Thinking {
+                thinking,
+                signature,
+            } => ResponseContentBlock::Thinking {
+                signature,
+                thinking,
+            },
+            ContentBlockStart::RedactedThinking { data } => {
+                ResponseContentBlock::RedactedThinking { data }
+            }
+            ContentBlockStart::ServerToolUse { id, name, input } => {
+                ResponseContentBlock::ServerToolUse { id, name, input }
+            }
+            ContentBlockStart::CodeExecutionToolResult {
+                tool_use_id,
+                content,
+            } => ResponseContentBlock::CodeExecutionToolResult {
+                tool_use_id,
+                content,
+            },
+            ContentBlockStart::WebSearchToolResult {
+                tool_use_id,
+                content,
+            } => ResponseContentBlock::WebSearchToolResult {
+                tool_use_id,
+                content,
+            },
         }
     }
 }
+
+// Builder convenience methods
+impl MessagesBuilder {
+    pub fn builder(model: &str, messages: impl Into<Vec<Message>>, max_tokens: u64) -> Self {
+        Self::create_empty()
+            .model(model)
+            .messages(messages)
+            .max_tokens(max_tokens)
+    }
+
+    /// Attaches a tool and forces Claude to use it.
+    ///
+    /// This is the usual setup for structured output: the tool's input schema
+    /// describes the shape you want back, and `tool_choice` is set to that tool
+    /// by name. Any previously attached tool with the same name is replaced so
+    /// the tool list and the tool choice always agree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// # use serde_json::json;
+    /// let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024).force_tool(Tool::Custom {
+    ///     name: "record_summary".to_string(),
+    ///     description: "Record a structured summary".to_string(),
+    ///     input_schema: json!({"type": "object"}),
+    /// });
+    /// ```
+    pub fn force_tool(mut self, tool: Tool) -> Self {
+        let name = tool.name().to_string();
+        let mut tools = self.tools.take().flatten().unwrap_or_default();
+        tools.retain(|existing| existing.name() != name);
+        tools.push(tool);
+        self.tools = Some(Some(tools));
+        self.tool_choice = Some(Some(ToolChoice::Tool { name }));
+        self
+    }
+
+    /// Adds a sequence where generation should stop.
+    ///
+    /// Can be called repeatedly; each call appends to any sequences already set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("List three fruits.")
+    ///     .stop_sequence("</list>")
+    ///     .stop_sequence("\n\nHuman:")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(request.stop_sequences.unwrap().len(), 2);
+    /// ```
+    pub fn stop_sequence(mut self, sequence: impl Into<String>) -> Self {
+        self.stop_sequences
+            .get_or_insert(None)
+            .get_or_insert_with(Vec::new)
+            .push(sequence.into());
+        self
+    }
+
+    /// Enables a beta feature for this request.
+    ///
+    /// Can be called repeatedly; all flags are sent comma-joined in the
+    /// `anthropic-beta` header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .beta("pdfs-2024-09-25")
+    ///     .beta("token-efficient-tools-2025-02-19");
+    /// ```
+    pub fn beta(mut self, flag: impl Into<String>) -> Self {
+        self.betas
+            .get_or_insert(None)
+            .get_or_insert_with(Vec::new)
+            .push(flag.into());
+        self
+    }
+
+    /// Enables extended thinking with a budget of `budget_tokens` thinking tokens.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16000)
+    ///     .user_text("Prove that there are infinitely many primes.")
+    ///     .enable_thinking(10000)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(request.thinking, Some(Thinking::Enabled { budget_tokens: 10000 }));
+    /// ```
+    pub fn enable_thinking(self, budget_tokens: u64) -> Self {
+        self.thinking(Thinking::Enabled { budget_tokens })
+    }
+
+    /// Appends a message to the conversation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .message(Message::user("Hello!"));
+    /// ```
+    pub fn message(mut self, message: Message) -> Self {
+        self.messages.get_or_insert_with(Vec::new).push(message);
+        self
+    }
+
+    /// Appends a user message containing only text to the conversation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("What is the capital of France?")
+    ///     .assistant_text("Paris.")
+    ///     .user_text("And of Germany?");
+    /// ```
+    pub fn user_text(self, text: impl Into<String>) -> Self {
+        self.message(Message::user(MessageContent::Text(text.into())))
+    }
+
+    /// Appends an assistant message containing only text to the conversation.
+    pub fn assistant_text(self, text: impl Into<String>) -> Self {
+        self.message(Message::assistant(MessageContent::Text(text.into())))
+    }
+
+    /// Prefills the start of the assistant's reply.
+    ///
+    /// Appends an assistant message that the model continues from, which is useful for
+    /// steering the output format, for example starting with `{` to force a JSON object.
+    /// Trailing whitespace is trimmed, as the API rejects a final assistant turn that ends
+    /// with whitespace, and a prefill that is only whitespace appends nothing.
+    ///
+    /// The response contains only the continuation, not the prefilled text, so prepend the
+    /// prefill when reading the complete reply. `stop_reason` and `usage.output_tokens`
+    /// likewise describe only the generated continuation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .credentials(Credentials::from_env())
+    ///     .user_text("Describe a cat as a JSON object.")
+    ///     .prefill("{")
+    ///     .create()
+    ///     .await?;
+    ///
+    /// let json = format!("{{{}", response.text());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prefill(self, text: impl Into<String>) -> Self {
+        let mut text = text.into();
+        text.truncate(text.trim_end().len());
+        if text.is_empty() {
+            return self;
+        }
+        self.assistant_text(text)
+    }
+
+    /// Sets `metadata.user_id`, an opaque identifier for the end user of the request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::messages::*;
+    /// let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_id("5d41402abc4b2a76b9719d911017c592")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     request.metadata,
+    ///     Some(Metadata::user("5d41402abc4b2a76b9719d911017c592"))
+    /// );
+    /// ```
+    pub fn user_id(self, user_id: impl Into<String>) -> Self {
+        self.metadata(Metadata::user(user_id))
+    }
+
+    /// Appends the assistant turn of a paused response, so that sending the request again
+    /// lets Claude pick up where it left off.
+    ///
+    /// A turn can be paused any number of times, so continue until the response is no
+    /// longer paused.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .credentials(Credentials::from_env())
+    ///     .user_text("Find this week's Rust release notes.")
+    ///     .tools(vec![Tool::WebSearch {
+    ///         max_uses: Some(5),
+    ///         allowed_domains: None,
+    ///         blocked_domains: None,
+    ///         user_location: None,
+    ///     }]);
+    ///
+    /// let mut response = builder.clone().create().await?;
+    /// while response.is_paused() {
+    ///     builder = builder.continue_from(&response);
+    ///     response = builder.clone().create().await?;
+    /// }
+    /// println!("{}", response.text());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn continue_from(self, response: &MessagesResponse) -> Self {
+        self.message(response.to_message())
+    }
+
+    /// Sends the request and runs the tools Claude asks for until it produces a final answer.
+    ///
+    /// The tool definitions are added to the request. Whenever Claude stops to use tools,
+    /// each tool use is dispatched by name to the matching handler, and the assistant turn
+    /// plus the tool results are appended to the conversation before sending it again.
+    /// Handler errors and unknown tool names are reported back to Claude as error results.
+    /// Paused turns are continued as with [`MessagesBuilder::continue_from`]. Returns the
+    /// first response that stops for any other reason. This is a shorthand for a
+    /// [`ToolRunner`] with synchronous handlers and the default iteration limit.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # use serde_json::json;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    /// let clock = ToolImpl::new(
+    ///     Tool::Custom {
+    ///         name: "current_time".to_string(),
+    ///         description: "Returns the current time".to_string(),
+    ///         input_schema: json!({"type": "object"}),
+    ///     },
+    ///     |_| Ok("12:00".to_string()),
+    /// );
+    ///
+    /// let response = MessagesBuilder::builder(
+    ///     "claude-3-7-sonnet-20250219",
+    ///     vec![Message::user("What time is it?")],
+    ///     1024,
+    /// )
+    /// .credentials(credentials)
+    /// .run_with_tools(vec![clock])
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run_with_tools(
+        self,
+        tools: Vec<ToolImpl>,
+    ) -> ApiResponseOrError<MessagesResponse> {
+        tools
+            .into_iter()
+            .fold(ToolRunner::new(), ToolRunner::tool_impl)
+            .run(self)
+            .await
+    }
+
+    /// Creates a new message request and returns the response.
+    ///
+    /// This is a convenience method that builds the request from the builder
+    /// and sends it to the Messages API.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(credentials.clone())
+    ///     .create()
+    ///     .await
+    ///     .unwrap();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(self) -> ApiResponseOrError<MessagesResponse> {
+        let request = self.build().map_err(builder_error)?;
+        MessagesResponse::create(request).await
+    }
+
+    /// Builds the request and counts its input tokens instead of sending it.
+    ///
+    /// See [`MessageTokensCount::create`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let count = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .count_tokens()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn count_tokens(self) -> ApiResponseOrError<MessageTokensCount> {
+        let request = self.build().map_err(builder_error)?;
+        MessageTokensCount::create(&request).await
+    }
+
+    /// Builds the request and sends it, blocking the current thread until the response
+    /// arrives.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .create_blocking()?;
+    /// println!("{}", response.text());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(self) -> ApiResponseOrError<MessagesResponse> {
+        let request = self.build().map_err(builder_error)?;
+        MessagesResponse::create_blocking(request)
+    }
+
+    /// Builds the request and sends it, abandoning it as soon as `token` is cancelled.
+    ///
+    /// See [`MessagesResponse::create_with_cancellation`].
+    #[cfg(feature = "cancellation")]
+    pub async fn create_with_cancellation(
+        self,
+        token: &CancellationToken,
+    ) -> ApiResponseOrError<MessagesResponse> {
+        let request = self.build().map_err(builder_error)?;
+        MessagesResponse::create_with_cancellation(request, token).await
+    }
+
+    /// Streams a structured output and resolves to the forced tool's input as `T`.
+    ///
+    /// The tool is attached and forced as with [`MessagesBuilder::force_tool`]. Streaming
+    /// events are accumulated and passed to `on_event` as they arrive, so progress can be
+    /// reported. As soon as the tool use block finishes streaming, its input is parsed and
+    /// deserialized into `T`; a mismatch with `T` is returned as a `json_parse_error`. An
+    /// `error` event sent mid-stream is returned as the API error it carries.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # use serde::Deserialize;
+    /// # use serde_json::json;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #[derive(Deserialize)]
+    /// struct Summary {
+    ///     title: String,
+    ///     points: Vec<String>,
+    /// }
+    ///
+    /// let credentials = Credentials::from_env();
+    /// let tool = Tool::Custom {
+    ///     name: "record_summary".to_string(),
+    ///     description: "Record a structured summary".to_string(),
+    ///     input_schema: json!({
+    ///         "type": "object",
+    ///         "properties": {
+    ///             "title": {"type": "string"},
+    ///             "points": {"type": "array", "items": {"type": "string"}}
+    ///         },
+    ///         "required": ["title", "points"]
+    ///     }),
+    /// };
+    ///
+    /// let summary: Summary = MessagesBuilder::builder(
+    ///     "claude-3-7-sonnet-20250219",
+    ///     vec![Message::user("Summarize the Rust book.")],
+    ///     1024,
+    /// )
+    /// .credentials(credentials)
+    /// .create_struct_stream(tool, |event| println!("{:?}", event))
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_struct_stream<T: DeserializeOwned>(
+        self,
+        tool: Tool,
+        mut on_event: impl FnMut(&StreamEvent),
+    ) -> ApiResponseOrError<T> {
+        let name = tool.name().to_string();
+        let stream = self.force_tool(tool).create_event_stream().await?;
+        let mut stream = std::pin::pin!(stream);
+
+        let mut accumulator = StreamAccumulator::new();
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            on_event(&event);
+            if let StreamEvent::Error { error } = event {
+                return Err(AnthropicErrorResponse::new(error.message, error.error_type));
+            }
+            accumulator.push(&event);
+            if let StreamEvent::ContentBlockStop { index } = event {
+                if let Some((tool_name, input)) = accumulator.tool_input(index as usize) {
+                    if tool_name == name {
+                        return serde_json::from_value(input?).map_err(|e| {
+                            AnthropicErrorResponse::new(
+                                format!("Tool input does not match the expected structure: {}", e),
+                                "json_parse_error".to_string(),
+                            )
+                        });
+                    }
+                }
+            }
+        }
+
+        Err(AnthropicErrorResponse::new(
+            format!("Stream ended before the {} tool input was complete", name),
+            "stream".to_string(),
+        ))
+    }
+
+    /// Creates a new streaming message request and returns a channel of events.
+    ///
+    /// This is a convenience method that builds the request from the builder
+    /// and sends it to the Messages API in streaming mode.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(credentials)
+    ///     .create_stream()
+    ///     .await?;
+    ///
+    /// while let Some(event) = stream.recv().await {
+    ///     // Process streaming events
+    ///     println!("{:?}", event?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub async fn create_stream(
+        self,
+    ) -> ApiResponseOrError<Receiver<ApiResponseOrError<StreamEvent>>> {
+        match self.build() {
+            Ok(request) => StreamEvent::create_stream(request).await,
+            Err(e) => Ok(error_channel(builder_error(e))),
+        }
+    }
+
+    /// Creates a new streaming message request and returns a [`Stream`] of events.
+    ///
+    /// This is the builder equivalent of [`StreamEvent::create_event_stream`] and does not
+    /// require the `runtime` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # use futures_util::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .create_event_stream()
+    ///     .await?;
+    ///
+    /// let mut stream = std::pin::pin!(stream);
+    /// while let Some(event) = stream.next().await {
+    ///     println!("{:?}", event?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_event_stream(
+        self,
+    ) -> ApiResponseOrError<impl Stream<Item = ApiResponseOrError<StreamEvent>>> {
+        open_event_stream(self.build().map_err(builder_error)).await
+    }
+
+    /// Streams the response, calling `on_event` for each event, and resolves to the
+    /// assembled [`MessagesResponse`].
+    ///
+    /// Events are read inline as the returned future is polled, so no task or channel is
+    /// involved; this works on wasm32 and can be raced with other futures in `select!`.
+    /// An `error` event from the API ends the stream with that error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # use std::io::Write;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+    ///     .user_text("Hello, Claude!")
+    ///     .credentials(Credentials::from_env())
+    ///     .stream_with(|event| {
+    ///         if let StreamEvent::ContentBlockDelta {
+    ///             delta: ContentBlockDelta::Text { text },
+    ///             ..
+    ///         } = event
+    ///         {
+    ///             print!("{}", text);
+    ///             std::io::stdout().flush().unwrap();
+    ///         }
+    ///     })
+    ///     .await?;
+    /// println!("\n{:?}", response.usage);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn stream_with(
+        self,
+        mut on_event: impl FnMut(&StreamEvent),
+    ) -> ApiResponseOrError<MessagesResponse> {
+        let stream = self.create_event_stream().await?;
+        let mut stream = std::pin::pin!(stream);
+
+        let mut accumulator = StreamAccumulator::new();
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            on_event(&event);
+            if let StreamEvent::Error { error } = event {
+                return Err(AnthropicErrorResponse::new(error.message, error.error_type));
+            }
+            accumulator.push(&event);
+        }
+        accumulator.into_response()
+    }
+}
+
+// Helper to create a builder with required fields
+impl MessagesResponse {
+    /// Creates a new builder with the required fields.
+    ///
+    /// This is a convenience method to create a builder with the
+    /// minimum required fields for a message request.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{messages::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let response =MessagesBuilder::builder(
+    ///     "claude-3-7-sonnet-20250219",
+    ///     vec![Message::user("Hello!")],
+    ///     100,
+    /// )
+    /// .credentials(credentials)
+    /// .create()
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(
+        model: &str,
+        messages: impl Into<Vec<Message>>,
+        max_tokens: u64,
+    ) -> MessagesBuilder {
+        MessagesBuilder::create_empty()
+            .model(model)
+            .messages(messages)
+            .max_tokens(max_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{http_response, json_response, sse_response, MockServer};
+
+    /// Collects formatted log output while the returned guard is alive.
+    #[cfg(feature = "tracing")]
+    #[derive(Clone)]
+    struct Capture(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl Capture {
+        fn start(level: tracing::Level) -> (Self, tracing::subscriber::DefaultGuard) {
+            let logs = Capture(Arc::default());
+            let writer = logs.clone();
+            let subscriber = tracing_subscriber::fmt()
+                .with_max_level(level)
+                .with_ansi(false)
+                .with_writer(move || writer.clone())
+                .finish();
+            (logs, tracing::subscriber::set_default(subscriber))
+        }
+
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_completion_event_has_token_counts() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{"id": "msg_01", "model": "claude-3-7-sonnet-20250219", "role": "assistant",
+                "content": [{"type": "text", "text": "Hi"}], "stop_reason": "end_turn",
+                "stop_sequence": null, "type": "message",
+                "usage": {"input_tokens": 12, "output_tokens": 34}}"#,
+        )])
+        .await;
+        let (logs, _guard) = Capture::start(tracing::Level::INFO);
+
+        MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .create()
+            .await
+            .unwrap();
+
+        let logs = logs.lines();
+        let event = logs
+            .iter()
+            .find(|line| line.contains("Message request completed"))
+            .unwrap();
+        assert!(event.contains("model=claude-3-7-sonnet-20250219"));
+        assert!(event.contains("input_tokens=12"));
+        assert!(event.contains("output_tokens=34"));
+        assert!(event.contains("duration_ms="));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_served_model_mismatch_warns() {
+        let response = |model: &str| {
+            json_response(
+                "200 OK",
+                &format!(
+                    r#"{{"id": "msg_01", "model": "{model}", "role": "assistant", "content": [],
+                        "stop_reason": "end_turn", "stop_sequence": null, "type": "message",
+                        "usage": {{"input_tokens": 1, "output_tokens": 1}}}}"#
+                ),
+            )
+        };
+        let server = MockServer::start(vec![
+            response("claude-3-7-sonnet-20250219"),
+            response("claude-3-7-sonnet-20250219"),
+            response("claude-3-5-sonnet-20241022"),
+        ])
+        .await;
+        let (logs, _guard) = Capture::start(tracing::Level::WARN);
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let mut served = Vec::new();
+        for model in [
+            "claude-3-7-sonnet-20250219",
+            "claude-3-7-sonnet-latest",
+            "claude-3-7-sonnet-20250219",
+        ] {
+            let response = MessagesBuilder::builder(model, [], 16)
+                .user_text("Hi")
+                .credentials(&credentials)
+                .create()
+                .await
+                .unwrap();
+            served.push(response.served_model().to_string());
+        }
+        assert_eq!(
+            served,
+            [
+                "claude-3-7-sonnet-20250219",
+                "claude-3-7-sonnet-20250219",
+                "claude-3-5-sonnet-20241022"
+            ]
+        );
+
+        let warnings: Vec<_> = logs
+            .lines()
+            .into_iter()
+            .filter(|line| line.contains("WARN"))
+            .collect();
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("requested=claude-3-7-sonnet-20250219"));
+        assert!(warnings[0].contains("served=claude-3-5-sonnet-20241022"));
+    }
+
+    #[tokio::test]
+    async fn test_continue_from_paused_turn() {
+        let server = MockServer::start(vec![
+            json_response(
+                "200 OK",
+                r#"{"id": "msg_01", "model": "claude-3-7-sonnet-20250219", "role": "assistant",
+                    "content": [{"type": "text", "text": "Searching..."}],
+                    "stop_reason": "pause_turn", "stop_sequence": null, "type": "message",
+                    "usage": {"input_tokens": 10, "output_tokens": 5}}"#,
+            ),
+            json_response(
+                "200 OK",
+                r#"{"id": "msg_02", "model": "claude-3-7-sonnet-20250219", "role": "assistant",
+                    "content": [{"type": "text", "text": "Found it."}],
+                    "stop_reason": "end_turn", "stop_sequence": null, "type": "message",
+                    "usage": {"input_tokens": 20, "output_tokens": 5}}"#,
+            ),
+        ])
+        .await;
+        let mut builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .user_text("Search the web.");
+
+        let mut response = builder.clone().create().await.unwrap();
+        assert!(response.is_paused());
+        while response.is_paused() {
+            builder = builder.continue_from(&response);
+            response = builder.clone().create().await.unwrap();
+        }
+
+        assert_eq!(response.text(), "Found it.");
+        let requests = server.requests();
+        assert!(requests[1]
+            .contains(r#"{"role":"assistant","content":[{"type":"text","text":"Searching..."}]}"#));
+    }
+
+    #[tokio::test]
+    async fn test_run_with_tools_calculator() {
+        let server = MockServer::start(vec![
+            json_response(
+                "200 OK",
+                r#"{
+                    "id": "msg_01",
+                    "model": "claude-3-7-sonnet-20250219",
+                    "role": "assistant",
+                    "content": [
+                        {"type": "text", "text": "Let me calculate that."},
+                        {"type": "tool_use", "id": "toolu_01", "name": "calculator", "input": {"operation": "add", "operands": [15, 27]}}
+                    ],
+                    "stop_reason": "tool_use",
+                    "stop_sequence": null,
+                    "type": "message",
+                    "usage": {"input_tokens": 10, "output_tokens": 5}
+                }"#,
+            ),
+            json_response(
+                "200 OK",
+                r#"{
+                    "id": "msg_02",
+                    "model": "claude-3-7-sonnet-20250219",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "15 + 27 = 42"}],
+                    "stop_reason": "end_turn",
+                    "stop_sequence": null,
+                    "type": "message",
+                    "usage": {"input_tokens": 20, "output_tokens": 5}
+                }"#,
+            ),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let calculator = ToolImpl::new(
+            Tool::Custom {
+                name: "calculator".to_string(),
+                description: "A calculator".to_string(),
+                input_schema: serde_json::json!({"type": "object"}),
+            },
+            |input| {
+                let operands = input["operands"]
+                    .as_array()
+                    .ok_or_else(|| anyhow::anyhow!("missing operands"))?;
+                let sum: f64 = operands.iter().filter_map(Value::as_f64).sum();
+                Ok(sum.to_string())
+            },
+        );
+
+        let response = MessagesBuilder::builder(
+            "claude-3-7-sonnet-20250219",
+            vec![Message {
+                role: MessageRole::User,
+                content: MessageContent::Text("What is 15 + 27?".to_string()),
+            }],
+            1024,
+        )
+        .credentials(credentials)
+        .run_with_tools(vec![calculator])
+        .await
+        .unwrap();
+
+        assert_eq!(response.id, "msg_02");
+        assert_eq!(response.stop_reason, Some(StopReason::EndTurn));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains(r#""tools":[{"name":"calculator""#));
+        assert!(requests[1].contains(
+            r#"{"type":"tool_use","id":"toolu_01","name":"calculator","input":{"operands":[15,27],"operation":"add"}}"#
+        ));
+        assert!(requests[1]
+            .contains(r#"{"type":"tool_result","tool_use_id":"toolu_01","content":"42"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_tool_runner_async_handler_and_iteration_limit() {
+        let tool_use = r#"{
+            "id": "msg_01",
+            "model": "claude-3-7-sonnet-20250219",
+            "role": "assistant",
+            "content": [{"type": "tool_use", "id": "toolu_01", "name": "lookup", "input": {"key": "a"}}],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        }"#;
+        let server = MockServer::start(vec![
+            json_response("200 OK", tool_use),
+            json_response("200 OK", tool_use),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let runner = ToolRunner::new()
+            .tool(
+                Tool::new(
+                    "lookup",
+                    "Looks up a key",
+                    serde_json::json!({"type": "object"}),
+                )
+                .unwrap(),
+                |input| async move {
+                    tokio::task::yield_now().await;
+                    Ok(format!(
+                        "value of {}",
+                        input["key"].as_str().unwrap_or_default()
+                    ))
+                },
+            )
+            .max_iterations(2);
+
+        let error = runner
+            .run(
+                MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+                    .credentials(credentials)
+                    .user_text("Look up a"),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.error.error_type, "tool_runner");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1]
+            .contains(r#"{"type":"tool_result","tool_use_id":"toolu_01","content":"value of a"}"#));
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_missing_builder_field_is_error() {
+        let error = MessagesBuilder::create_empty()
+            .model("claude-3-7-sonnet-20250219")
+            .messages(vec![])
+            .create()
+            .await
+            .unwrap_err();
+        assert_eq!(error.error.error_type, "builder");
+        assert!(error.error.message.contains("max_tokens"));
+
+        let mut stream = MessagesBuilder::create_empty()
+            .model("claude-3-7-sonnet-20250219")
+            .create_stream()
+            .await
+            .unwrap();
+        let error = stream.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.error.error_type, "builder");
+        assert!(stream.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_beta_header() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{
+                "id": "msg_01",
+                "model": "claude-3-7-sonnet-20250219",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi"}],
+                "stop_reason": "end_turn",
+                "stop_sequence": null,
+                "type": "message",
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            }"#,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .user_text("Hi")
+            .credentials(credentials)
+            .beta("pdfs-2024-09-25")
+            .beta("token-efficient-tools-2025-02-19")
+            .create()
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert!(request
+            .contains("anthropic-beta: pdfs-2024-09-25,token-efficient-tools-2025-02-19\r\n"));
+        assert!(!request.contains("betas"));
+    }
+
+    #[tokio::test]
+    async fn test_response_headers() {
+        let server = MockServer::start(vec![http_response(
+            "200 OK",
+            &[
+                ("content-type", "application/json"),
+                ("request-id", "req_018EeWyXxfu5pfWkrYcMdjWG"),
+                ("anthropic-organization-id", "org_123"),
+            ],
+            r#"{
+                "id": "msg_01",
+                "model": "claude-3-7-sonnet-20250219",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi"}],
+                "stop_reason": "end_turn",
+                "stop_sequence": null,
+                "type": "message",
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            }"#,
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .user_text("Hi")
+            .credentials(credentials)
+            .stream(true)
+            .create()
+            .await
+            .unwrap();
+
+        // `create` never asks for a streamed body, whatever the flag says.
+        assert!(!server.requests()[0].contains(r#""stream""#));
+        assert_eq!(
+            response.request_id.as_deref(),
+            Some("req_018EeWyXxfu5pfWkrYcMdjWG")
+        );
+        assert_eq!(response.organization_id.as_deref(), Some("org_123"));
+    }
+
+    #[test]
+    fn test_content_builder() {
+        let message = Message::user(
+            ContentBuilder::new()
+                .text("Compare these.")
+                .image(ImageSource {
+                    source_type: "base64".to_string(),
+                    media_type: MediaType::Png,
+                    data: "aW1n".to_string(),
+                })
+                .document(DocumentSource {
+                    source_type: "base64".to_string(),
+                    media_type: MediaType::Pdf,
+                    data: "cGRm".to_string(),
+                }),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "Compare these."},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "aW1n"}},
+                    {"type": "document", "source": {"type": "base64", "media_type": "application/pdf", "data": "cGRm"}}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_text_block_citations() {
+        let response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "model": "claude-3-7-sonnet-20250219",
+            "role": "assistant",
+            "content": [
+                {"type": "text", "text": "According to the contract, "},
+                {"type": "text", "text": "the term is two years.", "citations": [{
+                    "type": "page_location",
+                    "cited_text": "This agreement lasts two years.",
+                    "document_index": 0,
+                    "document_title": "Contract",
+                    "start_page_number": 3,
+                    "end_page_number": 4
+                }]}
+            ],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            &response.content[0],
+            ResponseContentBlock::Text {
+                citations: None,
+                ..
+            }
+        ));
+        match &response.content[1] {
+            ResponseContentBlock::Text {
+                citations: Some(citations),
+                ..
+            } => assert!(matches!(
+                &citations[..],
+                [Citation::PageLocation {
+                    start_page_number: 3,
+                    ..
+                }]
+            )),
+            other => panic!("unexpected block: {:?}", other),
+        }
+        assert_eq!(
+            response.text(),
+            "According to the contract, the term is two years."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_stream_events_do_not_end_the_stream() {
+        let server = MockServer::start(vec![sse_response(&[
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "future_event", "payload": {"anything": true}}"#,
+            r#"{"type": "message_stop"}"#,
+        ])])
+        .await;
+
+        let stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .create_event_stream()
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.map(Result::unwrap).collect().await;
+
+        assert!(matches!(events[0], StreamEvent::MessageStart { .. }));
+        assert_eq!(events[1], StreamEvent::Unknown);
+        assert_eq!(events[2], StreamEvent::MessageStop);
+    }
+
+    #[test]
+    fn test_citations_delta() {
+        let event: StreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "citations_delta", "citation": {"type": "char_location", "cited_text": "The grass is green.", "document_index": 0, "start_char_index": 0, "end_char_index": 20}}}"#,
+        )
+        .unwrap();
+
+        match event {
+            StreamEvent::ContentBlockDelta {
+                delta: ContentBlockDelta::CitationsDelta { citation },
+                ..
+            } => assert_eq!(
+                citation,
+                Citation::CharLocation {
+                    cited_text: "The grass is green.".to_string(),
+                    document_index: 0,
+                    document_title: None,
+                    start_char_index: 0,
+                    end_char_index: 20,
+                }
+            ),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_web_search_and_unknown_citations() {
+        let block: ResponseContentBlock = serde_json::from_value(serde_json::json!({
+            "type": "text",
+            "text": "Claude Shannon was born on April 30, 1916, in Petoskey, Michigan",
+            "citations": [
+                {
+                    "type": "web_search_result_location",
+                    "url": "https://en.wikipedia.org/wiki/Claude_Shannon",
+                    "title": "Claude Shannon - Wikipedia",
+                    "encrypted_index": "Eo8BCioIAhgBIiQyYjQ0OWJmZi1lNm",
+                    "cited_text": "Claude Elwood Shannon (April 30, 1916 – February 24, 2001) was an American mathematician"
+                },
+                {"type": "future_location", "cited_text": "Shannon", "anything": [1, 2]}
+            ]
+        }))
+        .unwrap();
+
+        let ResponseContentBlock::Text {
+            citations: Some(citations),
+            ..
+        } = block
+        else {
+            panic!("unexpected block: {:?}", block);
+        };
+        assert!(matches!(
+            &citations[0],
+            Citation::WebSearchResultLocation { url, title: Some(title), .. }
+                if url == "https://en.wikipedia.org/wiki/Claude_Shannon"
+                    && title == "Claude Shannon - Wikipedia"
+        ));
+        let unknown = serde_json::json!({"type": "future_location", "cited_text": "Shannon", "anything": [1, 2]});
+        assert_eq!(citations[1], Citation::Other(unknown.clone()));
+        assert_eq!(serde_json::to_value(&citations[1]).unwrap(), unknown);
+    }
+
+    #[test]
+    fn test_parse_tool_input() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct CalcArgs {
+            operation: String,
+            operands: Vec<i64>,
+        }
+
+        let input = serde_json::json!({"operation": "add", "operands": [15, 27]});
+        let tool_use = ToolUse {
+            id: "toolu_01",
+            name: "calculator",
+            input: &input,
+        };
+        assert_eq!(
+            tool_use.parse_input::<CalcArgs>().unwrap(),
+            CalcArgs {
+                operation: "add".to_string(),
+                operands: vec![15, 27],
+            }
+        );
+
+        let input = serde_json::json!({"operation": "add"});
+        let error = ToolUse {
+            input: &input,
+            ..tool_use
+        }
+        .parse_input::<CalcArgs>()
+        .unwrap_err();
+        assert_eq!(error.error.error_type, "json_parse_error");
+        assert!(error.error.message.contains("calculator"));
+    }
+
+    #[test]
+    fn test_image_source_from_file() {
+        let path = std::env::temp_dir().join(format!("anthropic-api-{}.PNG", std::process::id()));
+        std::fs::write(&path, b"\x89PNG\r\n").unwrap();
+        let source = ImageSource::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            source.unwrap(),
+            ImageSource {
+                source_type: "base64".to_string(),
+                media_type: MediaType::Png,
+                data: "iVBORw0K".to_string(),
+            }
+        );
+
+        let error = ImageSource::from_file("notes.pdf").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_thinking_serialization() {
+        assert_eq!(
+            serde_json::to_value(Thinking::Enabled {
+                budget_tokens: 1024
+            })
+            .unwrap(),
+            serde_json::json!({"type": "enabled", "budget_tokens": 1024})
+        );
+        assert_eq!(
+            serde_json::to_value(Thinking::Disabled).unwrap(),
+            serde_json::json!({"type": "disabled"})
+        );
+    }
+
+    #[test]
+    fn test_prefill_appends_trimmed_assistant_turn() {
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .user_text("Describe a cat as JSON.")
+            .prefill("{\n  ")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            request.messages.last(),
+            Some(&Message {
+                role: MessageRole::Assistant,
+                content: MessageContent::Text("{".to_string()),
+            })
+        );
+
+        let blank = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .user_text("Describe a cat as JSON.")
+            .prefill("  \n")
+            .build()
+            .unwrap();
+        assert_eq!(blank.messages.len(), 1);
+        assert_eq!(blank.messages[0].role, MessageRole::User);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_parameter_validation() {
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", "http://127.0.0.1:9/v1/"));
+
+        let error = builder.clone().temperature(2.5).create().await.unwrap_err();
+        assert_eq!(error.error.error_type, "validation");
+        assert!(error.error.message.contains("temperature"));
+
+        let error = builder.clone().top_p(f64::NAN).create().await.unwrap_err();
+        assert_eq!(error.error.error_type, "validation");
+
+        let error = builder.clone().top_k(0u32).create().await.unwrap_err();
+        assert_eq!(error.error.error_type, "validation");
+
+        let error = builder
+            .clone()
+            .stop_sequence("END")
+            .stop_sequence(" \n")
+            .create()
+            .await
+            .unwrap_err();
+        assert_eq!(error.error.error_type, "validation");
+        assert!(error.error.message.contains("stop_sequences"));
+
+        let request = builder.temperature(1.0).top_p(0.0).build().unwrap();
+        assert!(request.validate().is_ok());
+
+        let error = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .credentials(Credentials::new("test-key", "http://127.0.0.1:9/v1/"))
+            .create()
+            .await
+            .unwrap_err();
+        assert_eq!(error.error.error_type, "validation");
+        assert_eq!(error.error.message, "messages must not be empty");
+    }
+
+    #[test]
+    fn test_too_many_stop_sequences() {
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .user_text("Hi")
+            .stop_sequences(vec!["END".to_string(); MessagesRequest::MAX_STOP_SEQUENCES]);
+        assert!(builder.clone().build().unwrap().validate().is_ok());
+
+        let error = builder
+            .stop_sequence("STOP")
+            .build()
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.error.error_type, "validation");
+        assert!(error.error.message.contains("at most 8191"));
+    }
+
+    #[tokio::test]
+    async fn test_system_blocks_with_extended_cache_ttl() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            r#"{"id": "msg_01", "model": "claude-3-7-sonnet-20250219", "role": "assistant",
+                "content": [{"type": "text", "text": "Hi"}], "stop_reason": "end_turn",
+                "stop_sequence": null, "type": "message",
+                "usage": {"input_tokens": 1, "output_tokens": 1}}"#,
+        )])
+        .await;
+
+        MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .betas(vec!["output-128k-2025-02-19".to_string()])
+            .system(SystemPrompt::Blocks(vec![
+                SystemBlock::text("Be brief.").cache_control(CacheControl::ephemeral_1h())
+            ]))
+            .user_text("Hi")
+            .create()
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert!(request
+            .contains("anthropic-beta: output-128k-2025-02-19,extended-cache-ttl-2025-04-11\r\n"));
+        assert!(request.contains(
+            r#""system":[{"type":"text","text":"Be brief.","cache_control":{"type":"ephemeral","ttl":"1h"}}]"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_count_tokens_matches_request_body() {
+        let server = MockServer::start(vec![
+            json_response(
+                "200 OK",
+                r#"{"id": "msg_1", "type": "message", "role": "assistant", "model": "claude-3-7-sonnet-20250219", "content": [], "stop_reason": "end_turn", "stop_sequence": null, "usage": {"input_tokens": 42, "output_tokens": 1}}"#,
+            ),
+            json_response("200 OK", r#"{"input_tokens": 42}"#),
+        ])
+        .await;
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .system(SystemPrompt::Blocks(vec![
+                SystemBlock::text("Be brief.").cache_control(CacheControl::ephemeral_1h())
+            ]))
+            .tools(vec![Tool::Custom {
+                name: "calculator".to_string(),
+                description: "A calculator".to_string(),
+                input_schema: serde_json::json!({"type": "object"}),
+            }])
+            .tool_choice(ToolChoice::Auto)
+            .enable_thinking(1024)
+            .temperature(1.0)
+            .user_text("Hi")
+            .build()
+            .unwrap();
+
+        MessagesResponse::create(request.clone()).await.unwrap();
+        let count = MessageTokensCount::create(&request).await.unwrap();
+        assert_eq!(count.input_tokens, 42);
+
+        let requests = server.requests();
+        let body = |request: &str| -> serde_json::Map<String, Value> {
+            let (_, body) = request.split_once("\r\n\r\n").unwrap();
+            serde_json::from_str(body).unwrap()
+        };
+        let sent = body(&requests[0]);
+        let counted = body(&requests[1]);
+        assert!(requests[1].starts_with("POST /v1/messages/count_tokens "));
+        assert!(requests[1].contains("anthropic-beta: extended-cache-ttl-2025-04-11\r\n"));
+
+        let mut keys: Vec<_> = counted.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            [
+                "messages",
+                "model",
+                "system",
+                "thinking",
+                "tool_choice",
+                "tools"
+            ]
+        );
+        for (name, value) in &counted {
+            assert_eq!(Some(value), sent.get(name), "{name}");
+        }
+        assert!(sent.contains_key("max_tokens") && sent.contains_key("temperature"));
+    }
+
+    #[test]
+    fn test_plain_system_prompt_needs_no_beta() {
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .system("Be brief.")
+            .build()
+            .unwrap();
+        assert!(request.effective_betas().is_empty());
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["system"],
+            "Be brief."
+        );
+    }
+
+    #[test]
+    fn test_metadata_serialization() {
+        assert_eq!(
+            serde_json::to_value(Metadata::default()).unwrap(),
+            serde_json::json!({})
+        );
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .user_id("user-hash")
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["metadata"],
+            serde_json::json!({"user_id": "user-hash"})
+        );
+    }
+
+    #[test]
+    fn test_media_type() {
+        assert_eq!(MediaType::from_extension(".JPG"), Some(MediaType::Jpeg));
+        assert_eq!(MediaType::from_extension("webp"), Some(MediaType::Webp));
+        assert_eq!(MediaType::from_extension("bmp"), None);
+        assert_eq!(
+            serde_json::to_value(MediaType::Custom("image/heic".to_string())).unwrap(),
+            "image/heic"
+        );
+    }
+
+    #[test]
+    fn test_append_messages() {
+        let request = MessagesBuilder::create_empty()
+            .model("claude-3-7-sonnet-20250219")
+            .max_tokens(100u64)
+            .user_text("Hi")
+            .assistant_text("Hello!")
+            .message(Message {
+                role: MessageRole::User,
+                content: MessageContent::Text("Bye".to_string()),
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&request.messages).unwrap(),
+            serde_json::json!([
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello!"},
+                {"role": "user", "content": "Bye"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_service_tier() {
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .service_tier(ServiceTier::StandardOnly)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["service_tier"], "standard_only");
+
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json.get("service_tier").is_none());
+
+        let usage: Usage = serde_json::from_str(
+            r#"{"input_tokens": 5, "output_tokens": 1, "service_tier": "standard"}"#,
+        )
+        .unwrap();
+        assert_eq!(usage.service_tier.as_deref(), Some("standard"));
+        assert_eq!(usage.server_tool_use, None);
+    }
+
+    #[test]
+    fn test_accumulate_stream_usage() {
+        let events = [
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": [], "usage": {"input_tokens": 472, "output_tokens": 2, "cache_creation_input_tokens": 0, "cache_read_input_tokens": 128}}}"#,
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hello"}}"#,
+            r#"{"type": "content_block_stop", "index": 0}"#,
+            r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}, "usage": {"output_tokens": 15}}"#,
+            r#"{"type": "message_stop"}"#,
+        ];
+
+        let mut accumulator = StreamAccumulator::new();
+        for event in events {
+            accumulator.push(&serde_json::from_str(event).unwrap());
+        }
+
+        let expected = Usage {
+            input_tokens: 472,
+            output_tokens: 15,
+            cache_creation_input_tokens: Some(0),
+            cache_read_input_tokens: Some(128),
+            service_tier: None,
+            server_tool_use: None,
+        };
+        assert_eq!(accumulator.usage(), &expected);
+        assert_eq!(accumulator.into_response().unwrap().usage, expected);
+
+        // A delta without usage, or with only some counts, keeps what was reported earlier.
+        let mut accumulator = StreamAccumulator::new();
+        for event in [
+            events[0],
+            r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}}"#,
+            r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}, "usage": {"cache_read_input_tokens": 256}}"#,
+        ] {
+            accumulator.push(&serde_json::from_str(event).unwrap());
+        }
+        assert_eq!(
+            accumulator.usage(),
+            &Usage {
+                input_tokens: 472,
+                output_tokens: 2,
+                cache_creation_input_tokens: Some(0),
+                cache_read_input_tokens: Some(256),
+                service_tier: None,
+                server_tool_use: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_server_tool_usage() {
+        let usage: Usage = serde_json::from_str(
+            r#"{"input_tokens": 5, "output_tokens": 1, "server_tool_use": {"web_search_requests": 3}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            usage.server_tool_use,
+            Some(crate::ServerToolUsage {
+                web_search_requests: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_logprobs_preserved() {
+        let json = r#"{
+            "id": "msg_123",
+            "model": "claude-3-7-sonnet-20250219",
+            "role": "assistant",
+            "content": [{"type": "text", "text": "Hi"}],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 5, "output_tokens": 1},
+            "logprobs": {"tokens": ["Hi"], "token_logprobs": [-0.1]}
+        }"#;
+
+        let response: MessagesResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            response.logprobs,
+            Some(serde_json::json!({"tokens": ["Hi"], "token_logprobs": [-0.1]}))
+        );
+
+        let without: MessagesResponse =
+            serde_json::from_str(&json.replace(r#""logprobs""#, r#""other""#)).unwrap();
+        assert_eq!(without.logprobs, None);
+    }
+
+    #[test]
+    fn test_accumulate_tool_use_message() {
+        let events = [
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Let me "}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "calculate."}}"#,
+            r#"{"type": "content_block_stop", "index": 0}"#,
+            r#"{"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "calculator", "input": {}}}"#,
+            r#"{"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"operation\": \"add\", "}}"#,
+            r#"{"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"operands\": [15, 27]}"}}"#,
+            r#"{"type": "content_block_stop", "index": 1}"#,
+            r#"{"type": "message_delta", "delta": {"stop_reason": "tool_use", "stop_sequence": null}, "usage": {"input_tokens": 10, "output_tokens": 25}}"#,
+            r#"{"type": "message_stop"}"#,
+        ];
+
+        let mut accumulator = StreamAccumulator::new();
+        for event in events {
+            accumulator.push(&serde_json::from_str(event).unwrap());
+        }
+
+        let response = accumulator.clone().into_response().unwrap();
+        assert_eq!(response.stop_reason, Some(StopReason::ToolUse));
+        assert_eq!(response.usage.output_tokens, 25);
+
+        let message = accumulator.into_message().unwrap();
+        assert_eq!(
+            message,
+            Message {
+                role: MessageRole::Assistant,
+                content: MessageContent::ContentBlocks(vec![
+                    RequestContentBlock::Text {
+                        text: "Let me calculate.".to_string()
+                    },
+                    RequestContentBlock::ToolUse {
+                        id: "toolu_1".to_string(),
+                        name: "calculator".to_string(),
+                        input: serde_json::json!({"operation": "add", "operands": [15, 27]}),
+                    },
+                ]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_stop_reason_deserialization() {
+        let reasons: Vec<StopReason> = serde_json::from_str(
+            r#"["end_turn", "max_tokens", "stop_sequence", "tool_use", "pause_turn", "refusal", "model_context_window_exceeded", "something_new"]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            reasons,
+            vec![
+                StopReason::EndTurn,
+                StopReason::MaxTokens,
+                StopReason::StopSequence,
+                StopReason::ToolUse,
+                StopReason::PauseTurn,
+                StopReason::Refusal,
+                StopReason::ModelContextWindowExceeded,
+                StopReason::Other("something_new".to_string()),
+            ]
+        );
+        for reason in &reasons {
+            assert_eq!(StopReason::from(reason.as_str()), *reason);
+        }
+
+        let delta: MessageDelta =
+            serde_json::from_str(r#"{"stop_reason": "tool_use", "stop_sequence": null}"#).unwrap();
+        assert_eq!(delta.stop_reason, Some(StopReason::ToolUse));
+    }
+
+    #[test]
+    fn test_error_stream_event() {
+        let event: StreamEvent = serde_json::from_str(
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            event,
+            StreamEvent::Error {
+                error: AnthropicError {
+                    error_type: "overloaded_error".to_string(),
+                    message: "Overloaded".to_string(),
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_thinking_stream_events() {
+        let start: StreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            start,
+            StreamEvent::ContentBlockStart {
+                index: 0,
+                content_block: ContentBlockStart::Thinking {
+                    thinking: String::new(),
+                    signature: String::new(),
+                },
+            }
+        );
+
+        let redacted: StreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_start", "index": 1, "content_block": {"type": "redacted_thinking", "data": "abc"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            redacted,
+            StreamEvent::ContentBlockStart {
+                index: 1,
+                content_block: ContentBlockStart::RedactedThinking {
+                    data: "abc".to_string()
+                },
+            }
+        );
+
+        let thinking: StreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "Let me see"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            thinking,
+            StreamEvent::ContentBlockDelta {
+                index: 0,
+                delta: ContentBlockDelta::ThinkingDelta {
+                    thinking: "Let me see".to_string()
+                },
+            }
+        );
+
+        let signature: StreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "EqQB"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            signature,
+            StreamEvent::ContentBlockDelta {
+                index: 0,
+                delta: ContentBlockDelta::SignatureDelta {
+                    signature: "EqQB".to_string()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_thinking_blocks_echoed_in_request() {
+        let response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "model": "claude-3-7-sonnet-20250219",
+            "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "Let me think", "signature": "sig_abc"},
+                {"type": "redacted_thinking", "data": "opaque"},
+                {"type": "text", "text": "Done."}
+            ],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        }))
+        .unwrap();
+
+        let message = response.to_message();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "role": "assistant",
+                "content": [
+                    {"type": "thinking", "thinking": "Let me think", "signature": "sig_abc"},
+                    {"type": "redacted_thinking", "data": "opaque"},
+                    {"type": "text", "text": "Done."}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_web_search_response() {
+        let response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "role": "assistant",
+            "content": [
+                {"type": "text", "text": "I'll search for when Claude Shannon was born."},
+                {
+                    "type": "server_tool_use",
+                    "id": "srvtoolu_01WYG3ziw53XMcoyKL4XcZmE",
+                    "name": "web_search",
+                    "input": {"query": "claude shannon birth date"}
+                },
+                {
+                    "type": "web_search_tool_result",
+                    "tool_use_id": "srvtoolu_01WYG3ziw53XMcoyKL4XcZmE",
+                    "content": [{
+                        "type": "web_search_result",
+                        "url": "https://en.wikipedia.org/wiki/Claude_Shannon",
+                        "title": "Claude Shannon - Wikipedia",
+                        "encrypted_content": "EqgfCioIARgBIiQ3YTAwMjY1Mi1mZjM5LTQ1NGUtODgxNC1kNjNjNTk1ZWI3Y",
+                        "page_age": "April 30, 2025"
+                    }]
+                },
+                {"text": "Claude Shannon was born on April 30, 1916, in Petoskey, Michigan", "type": "text"}
+            ],
+            "id": "msg_a930390d3a",
+            "model": "claude-3-7-sonnet-20250219",
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 6039, "output_tokens": 931, "server_tool_use": {"web_search_requests": 1}}
+        }))
+        .unwrap();
+
+        let ResponseContentBlock::WebSearchToolResult {
+            tool_use_id,
+            content: WebSearchToolResultContent::Results(results),
+        } = &response.content[2]
+        else {
+            panic!("unexpected block: {:?}", response.content[2]);
+        };
+        assert_eq!(tool_use_id, "srvtoolu_01WYG3ziw53XMcoyKL4XcZmE");
+        assert_eq!(results[0].title, "Claude Shannon - Wikipedia");
+        assert_eq!(results[0].page_age.as_deref(), Some("April 30, 2025"));
+        assert_eq!(
+            response.content[2].to_string(),
+            "[web_search_tool_result 1 results]"
+        );
+        assert!(response.tool_uses().is_empty());
+
+        // The result is sent back unchanged when the turn is continued.
+        let message = response.to_message();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap()["content"][2],
+            serde_json::json!({
+                "type": "web_search_tool_result",
+                "tool_use_id": "srvtoolu_01WYG3ziw53XMcoyKL4XcZmE",
+                "content": [{
+                    "type": "web_search_result",
+                    "url": "https://en.wikipedia.org/wiki/Claude_Shannon",
+                    "title": "Claude Shannon - Wikipedia",
+                    "encrypted_content": "EqgfCioIARgBIiQ3YTAwMjY1Mi1mZjM5LTQ1NGUtODgxNC1kNjNjNTk1ZWI3Y",
+                    "page_age": "April 30, 2025"
+                }]
+            })
+        );
+
+        let error: ResponseContentBlock = serde_json::from_value(serde_json::json!({
+            "type": "web_search_tool_result",
+            "tool_use_id": "srvtoolu_02",
+            "content": {"type": "web_search_tool_result_error", "error_code": "max_uses_exceeded"}
+        }))
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "[web_search_tool_result_error max_uses_exceeded]"
+        );
+    }
+
+    #[test]
+    fn test_code_execution_response() {
+        let response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "model": "claude-sonnet-4-20250514",
+            "role": "assistant",
+            "container": {"id": "container_011", "expires_at": "2025-05-23T21:13:31.749448Z"},
+            "content": [
+                {"type": "server_tool_use", "id": "srvtoolu_1", "name": "code_execution", "input": {"code": "print(1 + 1)"}},
+                {
+                    "type": "code_execution_tool_result",
+                    "tool_use_id": "srvtoolu_1",
+                    "content": {"type": "code_execution_result", "stdout": "2\n", "stderr": "", "return_code": 0}
+                },
+                {
+                    "type": "code_execution_tool_result",
+                    "tool_use_id": "srvtoolu_2",
+                    "content": {"type": "code_execution_tool_result_error", "error_code": "unavailable"}
+                },
+                {"type": "text", "text": "The answer is 2."}
+            ],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        }))
+        .unwrap();
+
+        let container = response.container.as_ref().unwrap();
+        assert_eq!(container.id, "container_011");
+        assert!(container.expires_at_time().is_some());
+        assert_eq!(
+            response.content[1],
+            ResponseContentBlock::CodeExecutionToolResult {
+                tool_use_id: "srvtoolu_1".to_string(),
+                content: CodeExecutionResult::Result {
+                    stdout: "2\n".to_string(),
+                    stderr: String::new(),
+                    return_code: 0,
+                    content: vec![],
+                },
+            }
+        );
+        assert_eq!(
+            response.content[2].to_string(),
+            "[code_execution_tool_result_error unavailable]"
+        );
+        assert!(response.tool_uses().is_empty());
+
+        let message = response.to_message();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap()["content"][1],
+            serde_json::json!({
+                "type": "code_execution_tool_result",
+                "tool_use_id": "srvtoolu_1",
+                "content": {"type": "code_execution_result", "stdout": "2\n", "stderr": "", "return_code": 0, "content": []}
+            })
+        );
+
+        let request = MessagesBuilder::builder("claude-sonnet-4-20250514", [], 16)
+            .user_text("Hi")
+            .tools(vec![Tool::CodeExecution])
+            .container("container_011")
+            .build()
+            .unwrap();
+        assert_eq!(request.effective_betas(), vec![Tool::CODE_EXECUTION_BETA]);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["container"],
+            "container_011"
+        );
+    }
+
+    #[test]
+    fn test_accumulate_invalid_tool_input() {
+        let start = r#"{"type": "message_start", "message": {"id": "msg_1", "type": "message", "role": "assistant", "model": "claude-3-7-sonnet-20250219", "content": [], "stop_reason": null, "stop_sequence": null}}"#;
+        let accumulate = |events: &[&str]| {
+            let mut accumulator = StreamAccumulator::new();
+            for event in [start].iter().chain(events) {
+                accumulator.push(&serde_json::from_str(event).unwrap());
+            }
+            accumulator.into_response()
+        };
+
+        // The stream is cut off halfway through the tool input.
+        let error = accumulate(&[
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "calculator", "input": {}}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"operation\": \"ad"}}"#,
+        ])
+        .unwrap_err();
+        assert_eq!(error.error.error_type, "json_parse_error");
+        assert!(error
+            .error
+            .message
+            .starts_with("Failed to parse streamed input of tool calculator (toolu_1): "));
+        assert!(error
+            .error
+            .message
+            .ends_with(r#"; accumulated input: {"operation": "ad"#));
+
+        // A tool called without arguments streams an empty input.
+        let response = accumulate(&[
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": "toolu_2", "name": "get_time", "input": {}}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": ""}}"#,
+            r#"{"type": "content_block_stop", "index": 0}"#,
+        ])
+        .unwrap();
+        assert_eq!(response.tool_uses()[0].input, &serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_accumulate_code_execution_stream() {
+        let events = [
+            r#"{"type": "message_start", "message": {"id": "msg_1", "type": "message", "role": "assistant", "model": "claude-sonnet-4-20250514", "content": [], "stop_reason": null, "stop_sequence": null, "usage": {"input_tokens": 10, "output_tokens": 1}}}"#,
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "server_tool_use", "id": "srvtoolu_1", "name": "code_execution", "input": {}}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"code\": \"print(2)\"}"}}"#,
+            r#"{"type": "content_block_stop", "index": 0}"#,
+            r#"{"type": "content_block_start", "index": 1, "content_block": {"type": "code_execution_tool_result", "tool_use_id": "srvtoolu_1", "content": {"type": "code_execution_result", "stdout": "2\n", "stderr": "", "return_code": 0, "content": []}}}"#,
+            r#"{"type": "content_block_stop", "index": 1}"#,
+            r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null, "container": {"id": "container_011", "expires_at": "2025-05-23T21:13:31.749448Z"}}, "usage": {"output_tokens": 30}}"#,
+            r#"{"type": "message_stop"}"#,
+        ];
+        let mut accumulator = StreamAccumulator::new();
+        for event in events {
+            accumulator.push(&serde_json::from_str(event).unwrap());
+        }
+
+        let response = accumulator.into_response().unwrap();
+        assert_eq!(response.container.unwrap().id, "container_011");
+        assert_eq!(
+            response.content[0],
+            ResponseContentBlock::ServerToolUse {
+                id: "srvtoolu_1".to_string(),
+                name: "code_execution".to_string(),
+                input: serde_json::json!({"code": "print(2)"}),
+            }
+        );
+        assert!(matches!(
+            &response.content[1],
+            ResponseContentBlock::CodeExecutionToolResult {
+                content: CodeExecutionResult::Result { return_code: 0, .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_response_text_accessors() {
+        let response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "model": "claude-3-7-sonnet-20250219",
+            "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "Hmm", "signature": "sig"},
+                {"type": "text", "text": "Let me check. "},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}},
+                {"type": "text", "text": "One moment."}
+            ],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        }))
+        .unwrap();
+
+        assert_eq!(response.text(), "Let me check. One moment.");
+        assert_eq!(
+            response.text_blocks(),
+            vec!["Let me check. ", "One moment."]
+        );
+        let tool_uses = response.tool_uses();
+        assert_eq!(tool_uses.len(), 1);
+        assert_eq!(tool_uses[0].id, "toolu_1");
+        assert_eq!(tool_uses[0].name, "get_weather");
+        assert_eq!(tool_uses[0].input["city"], "Paris");
+        assert_eq!(response.stopped_on_sequence(), None);
+
+        assert_eq!(response.to_string(), "Let me check. One moment.");
+        let blocks: Vec<String> = response.content.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            blocks,
+            vec![
+                "[thinking]",
+                "Let me check. ",
+                r#"[tool_use get_weather {"city":"Paris"}]"#,
+                "One moment."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stopped_on_sequence() {
+        let mut response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "model": "claude-3-7-sonnet-20250219",
+            "role": "assistant",
+            "content": [{"type": "text", "text": "Section one"}],
+            "stop_reason": "stop_sequence",
+            "stop_sequence": "---",
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        }))
+        .unwrap();
+        assert_eq!(response.stopped_on_sequence(), Some("---"));
+
+        response.stop_reason = Some(StopReason::EndTurn);
+        assert_eq!(response.stopped_on_sequence(), None);
+        assert!(!response.is_refusal());
+
+        response.stop_reason = Some(StopReason::Refusal);
+        assert!(response.is_refusal());
+        assert!(!response.is_paused());
+        assert!(!response.hit_context_limit());
+
+        response.stop_reason = Some(StopReason::ModelContextWindowExceeded);
+        assert!(response.hit_context_limit());
+    }
+
+    #[test]
+    fn test_tool_name_validation() {
+        let schema = serde_json::json!({"type": "object"});
+        assert!(Tool::new("calc-v2_final", "", schema.clone()).is_ok());
+        assert!(Tool::new("a".repeat(64), "", schema.clone()).is_ok());
+
+        for name in ["", "has space", "ünïcode", "dot.name", &"a".repeat(65)] {
+            let error = Tool::new(name, "", schema.clone()).unwrap_err();
+            assert_eq!(error.name, name);
+        }
+
+        let error: AnthropicErrorResponse = Tool::new("bad name", "", schema).unwrap_err().into();
+        assert_eq!(error.error.error_type, "builder");
+    }
+
+    #[test]
+    fn test_tool_serialization() {
+        let tools = vec![
+            Tool::Custom {
+                name: "calculator".to_string(),
+                description: "A calculator".to_string(),
+                input_schema: serde_json::json!({"type": "object"}),
+            },
+            Tool::WebSearch {
+                max_uses: Some(5),
+                allowed_domains: Some(vec!["docs.rs".to_string()]),
+                blocked_domains: None,
+                user_location: Some(UserLocation {
+                    location_type: "approximate".to_string(),
+                    city: Some("London".to_string()),
+                    ..Default::default()
+                }),
+            },
+            Tool::Computer {
+                display_width_px: 1024,
+                display_height_px: 768,
+                display_number: None,
+            },
+            Tool::TextEditor,
+            Tool::Bash,
+            Tool::CodeExecution,
+        ];
+
+        assert_eq!(
+            serde_json::to_value(&tools).unwrap(),
+            serde_json::json!([
+                {"name": "calculator", "description": "A calculator", "input_schema": {"type": "object"}},
+                {
+                    "type": "web_search_20250305",
+                    "name": "web_search",
+                    "max_uses": 5,
+                    "allowed_domains": ["docs.rs"],
+                    "user_location": {"type": "approximate", "city": "London"}
+                },
+                {"type": "computer_20250124", "name": "computer", "display_width_px": 1024, "display_height_px": 768},
+                {"type": "text_editor_20250124", "name": "str_replace_editor"},
+                {"type": "bash_20250124", "name": "bash"},
+                {"type": "code_execution_20250522", "name": "code_execution"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_computer_use_tools_add_beta() {
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .beta(Tool::COMPUTER_USE_BETA)
+            .tools(vec![
+                Tool::Computer {
+                    display_width_px: 1024,
+                    display_height_px: 768,
+                    display_number: None,
+                },
+                Tool::TextEditor,
+                Tool::Bash,
+                Tool::CodeExecution,
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.effective_betas(),
+            vec![Tool::COMPUTER_USE_BETA, Tool::CODE_EXECUTION_BETA]
+        );
+
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .tools(vec![Tool::Bash])
+            .build()
+            .unwrap();
+        assert_eq!(request.effective_betas(), vec![Tool::COMPUTER_USE_BETA]);
+    }
+
+    #[test]
+    fn test_force_tool() {
+        let tool = Tool::Custom {
+            name: "record_summary".to_string(),
+            description: "Record a structured summary".to_string(),
+            input_schema: serde_json::json!({"type": "object"}),
+        };
+        let stale = Tool::Custom {
+            name: "record_summary".to_string(),
+            description: "An outdated definition".to_string(),
+            input_schema: serde_json::json!({"type": "object"}),
+        };
+
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .tools(vec![stale])
+            .force_tool(tool.clone())
+            .build()
+            .unwrap();
+
+        assert_eq!(request.tools, Some(vec![tool]));
+        assert_eq!(
+            request.tool_choice,
+            Some(ToolChoice::Tool {
+                name: "record_summary".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_response_round_trips_through_json() {
+        let response: MessagesResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "model": "claude-sonnet-4-20250514",
+            "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "Add them.", "signature": "sig"},
+                {"type": "text", "text": "Let me calculate that."},
+                {"type": "tool_use", "id": "toolu_1", "name": "calculator", "input": {"a": 15, "b": 27}}
+            ],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "type": "message",
+            "usage": {"input_tokens": 10, "output_tokens": 20, "service_tier": "standard"}
+        }))
+        .unwrap();
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MessagesResponse>(&json).unwrap(),
+            response
+        );
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["type"], "message");
+        assert_eq!(value["stop_reason"], "tool_use");
+        assert_eq!(value["content"][2]["type"], "tool_use");
+
+        let event: StreamEvent = serde_json::from_str(
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hi"}}"#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["delta"]["type"], "text_delta");
+        assert_eq!(serde_json::from_value::<StreamEvent>(json).unwrap(), event);
+    }
+
+    #[test]
+    fn test_tool_choice_none_keeps_tools() {
+        let tools = vec![Tool::Custom {
+            name: "calculator".to_string(),
+            description: "A calculator".to_string(),
+            input_schema: serde_json::json!({"type": "object"}),
+        }];
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+            .user_text("Hi")
+            .tools(tools);
+
+        let text_only = builder
+            .clone()
+            .tool_choice(ToolChoice::None)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&text_only).unwrap();
+        assert_eq!(json["tool_choice"], serde_json::json!({"type": "none"}));
+        assert_eq!(json["tools"][0]["name"], "calculator");
+
+        let json =
+            serde_json::to_value(builder.tool_choice(ToolChoice::Auto).build().unwrap()).unwrap();
+        assert_eq!(json["tool_choice"], serde_json::json!({"type": "auto"}));
+    }
+
+    #[tokio::test]
+    async fn test_simple_message() {
+        let credentials = Credentials::from_env();
+
+        let response = MessagesResponse::builder(
+            "claude-3-7-sonnet-20250219",
+            vec![Message {
+                role: MessageRole::User,
+                content: MessageContent::Text("Hello!".to_string()),
+            }],
+            100,
+        )
+        .credentials(credentials)
+        .create()
+        .await
+        .unwrap();
+
+        assert!(!response.content.is_empty());
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_streaming_message() {
+        let credentials = Credentials::from_env();
+
+        let mut stream = MessagesResponse::builder(
+            "claude-3-7-sonnet-20250219",
+            vec![Message {
+                role: MessageRole::User,
+                content: MessageContent::Text("Hello!".to_string()),
+            }],
+            100,
+        )
+        .credentials(credentials)
+        .create_stream()
+        .await
+        .unwrap();
+
+        while let Some(event) = stream.recv().await {
+            if let StreamEvent::ContentBlockDelta {
+                delta: ContentBlockDelta::Text { text },
+                ..
+            } = event.unwrap()
+            {
+                print!("{}", text);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_struct_stream() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Calculation {
+            operation: String,
+            operands: Vec<i64>,
+        }
+
+        let server = MockServer::start(vec![sse_response(&[
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": "toolu_01", "name": "calculator", "input": {}}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"operation\": \"add\", "}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "\"operands\": [15, 27]}"}}"#,
+            r#"{"type": "content_block_stop", "index": 0}"#,
+            r#"{"type": "message_delta", "delta": {"stop_reason": "tool_use", "stop_sequence": null}, "usage": {"output_tokens": 20}}"#,
+            r#"{"type": "message_stop"}"#,
+        ])])
+        .await;
+
+        let tool = Tool::Custom {
+            name: "calculator".to_string(),
+            description: "A calculator".to_string(),
+            input_schema: serde_json::json!({"type": "object"}),
+        };
+        let mut events = 0;
+        let calculation: Calculation =
+            MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+                .user_text("Hi")
+                .credentials(Credentials::new("test-key", server.base_url.as_str()))
+                .create_struct_stream(tool, |_| events += 1)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            calculation,
+            Calculation {
+                operation: "add".to_string(),
+                operands: vec![15, 27],
+            }
+        );
+        // Resolved as soon as the tool block stopped, before the message delta arrived.
+        assert_eq!(events, 5);
+        assert!(
+            server.requests()[0].contains(r#""tool_choice":{"type":"tool","name":"calculator"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_struct_stream_error_event() {
+        let server = MockServer::start(vec![sse_response(&[
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": "toolu_01", "name": "calculator", "input": {}}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"operation\": "}}"#,
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#,
+        ])])
+        .await;
+
+        let tool = Tool::Custom {
+            name: "calculator".to_string(),
+            description: "A calculator".to_string(),
+            input_schema: serde_json::json!({"type": "object"}),
+        };
+        let mut events = 0;
+        let error = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .create_struct_stream::<serde_json::Value>(tool, |_| events += 1)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.error.error_type, "overloaded_error");
+        assert_eq!(error.error.message, "Overloaded");
+        assert_eq!(events, 4);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_stream_parse_error_is_delivered() {
+        let server = MockServer::start(vec![sse_response(&[
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "not valid json"#,
+        ])])
+        .await;
+
+        let mut stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .create_stream()
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            stream.recv().await,
+            Some(Ok(StreamEvent::MessageStart { .. }))
+        ));
+        let error = stream.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.error.error_type, "json_parse_error");
+        assert!(stream.recv().await.is_none());
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_base_url_override() {
+        let body = r#"{"id": "msg_1", "type": "message", "role": "assistant", "model": "claude-3-7-sonnet-20250219", "content": [], "stop_reason": "end_turn", "stop_sequence": null, "usage": {"input_tokens": 1, "output_tokens": 1}}"#;
+        let anthropic = MockServer::start(vec![json_response("200 OK", body)]).await;
+        let backend = MockServer::start(vec![
+            json_response("200 OK", body),
+            sse_response(&[r#"{"type": "message_stop"}"#]),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", anthropic.base_url.as_str());
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 16)
+            .credentials(&credentials)
+            .user_text("Hi");
+
+        builder
+            .clone()
+            .base_url(backend.base_url.as_str())
+            .create()
+            .await
+            .unwrap();
+        let mut stream = builder
+            .clone()
+            .base_url(backend.base_url.as_str())
+            .create_stream()
+            .await
+            .unwrap();
+        while stream.recv().await.is_some() {}
+        builder.create().await.unwrap();
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| request.starts_with("POST /v1/messages ")
+                && request.contains("x-api-key: test-key\r\n")));
+        assert_eq!(anthropic.requests().len(), 1);
+        assert_eq!(credentials.base_url(), anthropic.base_url);
+    }
+
+    #[tokio::test]
+    async fn test_stream_with_callback() {
+        let server = MockServer::start(vec![
+            sse_response(&[
+                r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": [], "usage": {"input_tokens": 10, "output_tokens": 1}}}"#,
+                r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}"#,
+                r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hello"}}"#,
+                r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": " there"}}"#,
+                r#"{"type": "content_block_stop", "index": 0}"#,
+                r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}, "usage": {"output_tokens": 5}}"#,
+                r#"{"type": "message_stop"}"#,
+            ]),
+            sse_response(&[
+                r#"{"type": "message_start", "message": {"id": "msg_124", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+                r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#,
+            ]),
+        ])
+        .await;
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()));
+
+        let mut text = String::new();
+        let response = builder
+            .clone()
+            .stream_with(|event| {
+                if let StreamEvent::ContentBlockDelta {
+                    delta: ContentBlockDelta::Text { text: delta },
+                    ..
+                } = event
+                {
+                    text.push_str(delta);
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello there");
+        assert_eq!(response.text(), "Hello there");
+        assert_eq!(response.usage.output_tokens, 5);
+        assert_eq!(response.stop_reason, Some(StopReason::EndTurn));
+
+        let mut events = 0;
+        let error = builder.stream_with(|_| events += 1).await.unwrap_err();
+        assert_eq!(error.error.error_type, "overloaded_error");
+        assert_eq!(events, 2);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_create_stream_forces_stream_flag() {
+        let server = MockServer::start(vec![sse_response(&[r#"{"type": "message_stop"}"#])]).await;
+
+        let request = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .build()
+            .unwrap();
+        assert_eq!(request.stream, None);
+
+        let mut stream = StreamEvent::create_stream(request).await.unwrap();
+        assert!(matches!(
+            stream.recv().await,
+            Some(Ok(StreamEvent::MessageStop))
+        ));
+        assert!(server.requests()[0].contains(r#""stream":true"#));
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_stream_idle_timeout() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A server that sends one event and then goes silent without closing the connection.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0u8; 4096]).await;
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\n\
+                      data: {\"type\": \"ping\"}\n\n",
+                )
+                .await;
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        });
+
+        let mut stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", base_url.as_str()))
+            .stream_idle_timeout(Duration::from_millis(200))
+            .create_stream()
+            .await
+            .unwrap();
+
+        let error = stream.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.error.error_type, "stream");
+        assert!(stream.recv().await.is_none());
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_dropping_stream_closes_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A server that sends one event and then waits for the client to hang up.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0u8; 4096]).await;
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\n\
+                      data: {\"type\": \"message_stop\"}\n\n",
+                )
+                .await;
+            socket.read(&mut [0u8; 4096]).await.unwrap_or(0)
+        });
+
+        let mut stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", base_url.as_str()))
+            .create_stream()
+            .await
+            .unwrap();
+        stream.recv().await.unwrap().unwrap();
+        drop(stream);
+
+        let read = tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("connection was not closed")
+            .unwrap();
+        assert_eq!(read, 0);
+    }
+
+    #[cfg(feature = "cancellation")]
+    #[tokio::test]
+    async fn test_cancelled_request_closes_connection() {
+        use tokio::io::AsyncReadExt;
+
+        // A server that never responds and reports when the client hangs up.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1/", listener.local_addr().unwrap());
+        let (received_tx, received_rx) = tokio::sync::oneshot::channel();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0u8; 4096]).await;
+            let _ = received_tx.send(());
+            socket.read(&mut [0u8; 4096]).await.unwrap_or(0)
+        });
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            let _ = received_rx.await;
+            canceller.cancel();
+        });
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", base_url.as_str()));
+
+        let error = builder
+            .clone()
+            .create_with_cancellation(&token)
+            .await
+            .unwrap_err();
+        assert_eq!(error.error.error_type, "cancelled");
+
+        let read = tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("connection was not closed")
+            .unwrap();
+        assert_eq!(read, 0);
+
+        // An already cancelled token fails without connecting.
+        let error = builder.create_with_cancellation(&token).await.unwrap_err();
+        assert_eq!(error.error.error_type, "cancelled");
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_stream_ends_cleanly_after_message_stop() {
+        // The body is cut short after the final event, as when the connection is reset
+        // during teardown, which the event source reports as a transport error.
+        let events = [
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "message_stop"}"#,
+        ];
+        let response = sse_response(&events);
+        let body_len = response.split_once("\r\n\r\n").unwrap().1.len();
+        let truncated = response.replace(
+            &format!("content-length: {}", body_len),
+            &format!("content-length: {}", body_len + 64),
+        );
+        let server = MockServer::start(vec![truncated.clone(), truncated]).await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let mut rx = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(credentials.clone())
+            .create_stream()
+            .await
+            .unwrap();
+        let mut received = Vec::new();
+        while let Some(event) = rx.recv().await {
+            received.push(event.unwrap());
+        }
+        assert_eq!(received.last(), Some(&StreamEvent::MessageStop));
+
+        let stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(credentials)
+            .create_event_stream()
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_stream_cut_before_message_stop_is_error() {
+        let events = sse_response(&[
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}"#,
+            r#"{"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hel"}}"#,
+        ]);
+        let server = MockServer::start(vec![events.clone(), events]).await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+        let builder = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(credentials);
+
+        let stream = builder.clone().create_event_stream().await.unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert_eq!(events.len(), 4);
+        assert!(events[..3].iter().all(Result::is_ok));
+        let error = events[3].as_ref().unwrap_err();
+        assert_eq!(error.error.error_type, "stream");
+        assert!(error.error.message.contains("message_stop"));
+
+        let error = builder.stream_with(|_| {}).await.unwrap_err();
+        assert_eq!(error.error.error_type, "stream");
+    }
+
+    #[tokio::test]
+    async fn test_create_event_stream() {
+        let server = MockServer::start(vec![sse_response(&[
+            r#"{"type": "message_start", "message": {"id": "msg_123", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": []}}"#,
+            r#"{"type": "ping"}"#,
+            r#"{"type": "message_stop"}"#,
+        ])])
+        .await;
+
+        let stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .create_event_stream()
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.collect().await;
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], Ok(StreamEvent::MessageStart { .. })));
+        assert!(matches!(events[1], Ok(StreamEvent::MessageStop)));
+        assert!(server.requests()[0].contains(r#""stream":true"#));
+
+        let events: Vec<_> = MessagesBuilder::create_empty()
+            .create_event_stream()
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].as_ref().unwrap_err().error.error_type, "builder");
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_stream_http_error_is_delivered() {
+        let server = MockServer::start(vec![json_response(
+            "529 Overloaded",
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#,
+        )])
+        .await;
+
+        let mut stream = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 100)
+            .user_text("Hi")
+            .credentials(Credentials::new("test-key", server.base_url.as_str()))
+            .create_stream()
+            .await
+            .unwrap();
+
+        let error = stream.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.error.error_type, "overloaded_error");
+        assert!(stream.recv().await.is_none());
+    }
+}
@@ -7,7 +7,8 @@
 //!
 //! - List all available models with pagination support
 //! - Get detailed information about a specific model
-//! - Resolve model aliases to model IDs
+//! - Resolve model aliases to model IDs with [`Model::resolve`]
+//! - Look up the `max_tokens` limit of known models with [`Model::max_output_tokens_for`]
 //!
 //! ## Basic Usage
 //!
@@ -38,27 +39,40 @@
 //! }
 //! ```
 
-use crate::{anthropic_request_json, ApiResponseOrError, Credentials};
+#[cfg(feature = "blocking")]
+use crate::block_on;
+use crate::pagination::{impl_paginated, validate_limit};
+use crate::timestamp::{to_system_time, Timestamp};
+use crate::{anthropic_request_json, builder_error, ApiResponseOrError, Credentials};
+#[cfg(feature = "runtime")]
+use crate::{error_channel, pagination::Paginated};
 use derive_builder::Builder;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::Receiver;
+
+/// A `max_tokens` value accepted by every model, for models missing from
+/// [`Model::max_output_tokens_for`].
+pub const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 4096;
 
 /// A model available through the Anthropic API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Model {
     /// Unique model identifier
     pub id: String,
     /// A human-readable name for the model
     pub display_name: String,
     /// RFC 3339 datetime string representing the time at which the model was released
-    pub created_at: String,
+    pub created_at: Timestamp,
     /// Object type (always "model" for Models)
     #[serde(rename = "type")]
     pub model_type: String,
 }
 
 /// Response from the List Models API.
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct ModelList {
     /// List of available models
     pub data: Vec<Model>,
@@ -92,12 +106,19 @@ pub struct ModelListRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<u32>,
 
+    /// Additional query parameters sent as-is, for filters not yet modelled by this crate
+    #[serde(skip_serializing)]
+    #[builder(default)]
+    pub extra_query: Vec<(String, String)>,
+
     /// Credentials for authentication (not serialized)
     #[serde(skip_serializing)]
     #[builder(default)]
     pub credentials: Option<Credentials>,
 }
 
+impl_paginated!(ModelList, Model, ModelListRequest, ModelListBuilder);
+
 /// Request parameters for getting a specific model.
 #[derive(Serialize, Builder, Debug, Clone)]
 #[builder(derive(Clone, Debug, PartialEq))]
@@ -150,6 +171,7 @@ impl ModelList {
     ///     before_id: None,
     ///     after_id: None,
     ///     limit: Some(20),
+    ///     extra_query: Vec::new(),
     ///     credentials: Some(credentials),
     /// };
     ///
@@ -158,6 +180,7 @@ impl ModelList {
     /// # }
     /// ```
     pub async fn create(request: ModelListRequest) -> ApiResponseOrError<Self> {
+        validate_limit(request.limit)?;
         let credentials_opt = request.credentials.clone();
 
         // Build query parameters
@@ -175,14 +198,28 @@ impl ModelList {
         anthropic_request_json(
             Method::GET,
             "models",
-            |r| r.query(&query_params),
+            |r| r.query(&query_params).query(&request.extra_query),
             credentials_opt,
         )
         .await
     }
+
+    /// Lists models like [`ModelList::create`], blocking the current thread until it completes.
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(request: ModelListRequest) -> ApiResponseOrError<Self> {
+        block_on(Self::create(request))
+    }
 }
 
 impl Model {
+    /// Value of the object `type` field for models.
+    pub const TYPE: &'static str = "model";
+
+    /// Parses `created_at` into a [`SystemTime`], or `None` if the timestamp is malformed.
+    pub fn created_at_time(&self) -> Option<SystemTime> {
+        to_system_time(&self.created_at)
+    }
+
     /// Creates a builder for getting a specific model.
     ///
     /// # Example
@@ -228,6 +265,76 @@ impl Model {
 
         anthropic_request_json(Method::GET, &route, |r| r, credentials_opt).await
     }
+
+    /// Gets a model like [`Model::create`], blocking the current thread until it completes.
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(request: ModelRequest) -> ApiResponseOrError<Self> {
+        block_on(Self::create(request))
+    }
+
+    /// Resolves a model alias such as `claude-3-7-sonnet-latest` to its concrete model ID.
+    ///
+    /// Passing a model ID that is already concrete returns it unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{models::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let id = Model::resolve("claude-3-7-sonnet-latest", Some(Credentials::from_env())).await?;
+    /// println!("Pinning {}", id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn resolve(
+        alias: impl Into<String>,
+        credentials: Option<Credentials>,
+    ) -> ApiResponseOrError<String> {
+        let request = ModelRequest {
+            model_id: alias.into(),
+            credentials,
+        };
+        Ok(Model::create(request).await?.id)
+    }
+
+    /// Returns the largest `max_tokens` this model accepts, if it is a known model.
+    ///
+    /// See [`Model::max_output_tokens_for`].
+    pub fn max_output_tokens(&self) -> Option<u64> {
+        Self::max_output_tokens_for(&self.id)
+    }
+
+    /// Returns the largest `max_tokens` the given model ID or alias accepts, if it is known.
+    ///
+    /// The limits come from a table of known models rather than the API, so newer models
+    /// return `None`; fall back to [`DEFAULT_MAX_OUTPUT_TOKENS`], which every model accepts.
+    /// Limits raised by beta headers, such as `output-128k-2025-02-19`, are not reflected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anthropic_api::models::*;
+    /// let limit = Model::max_output_tokens_for("claude-3-5-haiku-20241022")
+    ///     .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+    /// let max_tokens = 20_000u64.min(limit);
+    /// assert_eq!(max_tokens, 8192);
+    /// ```
+    pub fn max_output_tokens_for(model_id: &str) -> Option<u64> {
+        let limit = match model_id {
+            "claude-opus-4-20250514" | "claude-opus-4-0" => 32_000,
+            "claude-sonnet-4-20250514" | "claude-sonnet-4-0" => 64_000,
+            "claude-3-7-sonnet-20250219" | "claude-3-7-sonnet-latest" => 64_000,
+            "claude-3-5-sonnet-20241022"
+            | "claude-3-5-sonnet-20240620"
+            | "claude-3-5-sonnet-latest" => 8192,
+            "claude-3-5-haiku-20241022" | "claude-3-5-haiku-latest" => 8192,
+            "claude-3-opus-20240229" | "claude-3-opus-latest" => 4096,
+            "claude-3-sonnet-20240229" | "claude-3-haiku-20240307" => 4096,
+            _ => return None,
+        };
+        Some(limit)
+    }
 }
 
 // Builder convenience methods
@@ -254,9 +361,60 @@ impl ModelListBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<ModelList> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         ModelList::create(request).await
     }
+
+    /// Builds the request and lists models, blocking the current thread until it completes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{models::*, Credentials};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let models = ModelList::builder()
+    ///     .credentials(Credentials::from_env())
+    ///     .create_blocking()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(self) -> ApiResponseOrError<ModelList> {
+        let request = self.build().map_err(builder_error)?;
+        ModelList::create_blocking(request)
+    }
+
+    /// Lists all models, transparently fetching subsequent pages until there are no more.
+    ///
+    /// Every page is requested with the same credentials and limit. Items are delivered on
+    /// the returned channel; if a page fails to load, the error is the final item.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_api::{models::*, Credentials};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = Credentials::from_env();
+    ///
+    /// let mut models = ModelList::builder()
+    ///     .credentials(credentials)
+    ///     .limit(100u32)
+    ///     .into_stream();
+    ///
+    /// while let Some(model) = models.recv().await {
+    ///     println!("{:?}", model?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "runtime")]
+    pub fn into_stream(self) -> Receiver<ApiResponseOrError<Model>> {
+        match self.build() {
+            Ok(request) => ModelList::paginate(request),
+            Err(e) => error_channel(builder_error(e)),
+        }
+    }
 }
 
 impl ModelBuilder {
@@ -281,16 +439,193 @@ impl ModelBuilder {
     /// # }
     /// ```
     pub async fn create(self) -> ApiResponseOrError<Model> {
-        let request = self.build().unwrap();
+        let request = self.build().map_err(builder_error)?;
         Model::create(request).await
     }
+
+    /// Builds the request and gets the model, blocking the current thread until it completes.
+    #[cfg(feature = "blocking")]
+    pub fn create_blocking(self) -> ApiResponseOrError<Model> {
+        let request = self.build().map_err(builder_error)?;
+        Model::create_blocking(request)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pagination::Cursor;
+    use crate::tests::{json_response, MockServer};
     use crate::Credentials;
 
+    fn model_json(id: &str) -> String {
+        format!(
+            r#"{{"id": "{id}", "display_name": "{id}", "created_at": "2025-02-19T00:00:00Z", "type": "model"}}"#
+        )
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn test_list_models_into_stream() {
+        let server = MockServer::start(vec![
+            json_response(
+                "200 OK",
+                &format!(
+                    r#"{{"data": [{}, {}], "first_id": "a", "last_id": "b", "has_more": true}}"#,
+                    model_json("a"),
+                    model_json("b")
+                ),
+            ),
+            json_response(
+                "200 OK",
+                &format!(
+                    r#"{{"data": [{}], "first_id": "c", "last_id": "c", "has_more": false}}"#,
+                    model_json("c")
+                ),
+            ),
+        ])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let mut stream = ModelList::builder()
+            .credentials(credentials)
+            .limit(2u32)
+            .into_stream();
+        let mut ids = Vec::new();
+        while let Some(model) = stream.recv().await {
+            ids.push(model.unwrap().id);
+        }
+        assert_eq!(ids, vec!["a", "b", "c"]);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("GET /v1/models?limit=2 "));
+        assert!(requests[1].starts_with("GET /v1/models?after_id=b&limit=2 "));
+        assert!(requests
+            .iter()
+            .all(|request| request.contains("x-api-key: test-key")));
+    }
+
+    #[tokio::test]
+    async fn test_page_cursors() {
+        let page: ModelList = serde_json::from_str(&format!(
+            r#"{{"data": [{}, {}], "first_id": "a", "last_id": "b", "has_more": true}}"#,
+            model_json("a"),
+            model_json("b")
+        ))
+        .unwrap();
+        assert_eq!(
+            page.next_page_cursor(),
+            Some(Cursor::After("b".to_string()))
+        );
+        assert_eq!(
+            page.prev_page_cursor(),
+            Some(Cursor::Before("a".to_string()))
+        );
+
+        let request = page
+            .prev_page_builder()
+            .unwrap()
+            .limit(2u32)
+            .cursor(Cursor::After("b".to_string()))
+            .build()
+            .unwrap();
+        assert_eq!(request.after_id.as_deref(), Some("b"));
+        assert_eq!(request.before_id, None);
+
+        let empty: ModelList = serde_json::from_str(
+            r#"{"data": [], "first_id": null, "last_id": null, "has_more": false}"#,
+        )
+        .unwrap();
+        assert!(empty.next_page_builder().is_none());
+
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            &format!(
+                r#"{{"data": [{}], "first_id": "c", "last_id": "c", "has_more": false}}"#,
+                model_json("c")
+            ),
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+        page.next_page_builder()
+            .unwrap()
+            .credentials(credentials)
+            .create()
+            .await
+            .unwrap();
+        assert!(server.requests()[0].starts_with("GET /v1/models?after_id=b "));
+    }
+
+    #[tokio::test]
+    async fn test_list_limit_out_of_range() {
+        let server = MockServer::start(vec![]).await;
+        for limit in [0u32, 1001] {
+            let error = ModelList::builder()
+                .credentials(Credentials::new("test-key", server.base_url.as_str()))
+                .limit(limit)
+                .create()
+                .await
+                .unwrap_err();
+            assert_eq!(error.error.error_type, "validation");
+            assert_eq!(
+                error.error.message,
+                format!("limit must be between 1 and 1000, got {limit}")
+            );
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_alias() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            &model_json("claude-3-7-sonnet-20250219"),
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let id = Model::resolve("claude-3-7-sonnet-latest", Some(credentials))
+            .await
+            .unwrap();
+        assert_eq!(id, "claude-3-7-sonnet-20250219");
+        assert!(server.requests()[0].starts_with("GET /v1/models/claude-3-7-sonnet-latest "));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[tokio::test]
+    async fn test_get_model_blocking() {
+        let server = MockServer::start(vec![json_response(
+            "200 OK",
+            &model_json("claude-3-7-sonnet-20250219"),
+        )])
+        .await;
+        let credentials = Credentials::new("test-key", server.base_url.as_str());
+
+        let model = tokio::task::spawn_blocking(move || {
+            Model::builder("claude-3-7-sonnet-20250219")
+                .credentials(credentials)
+                .create_blocking()
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(model.id, "claude-3-7-sonnet-20250219");
+    }
+
+    #[test]
+    fn test_max_output_tokens() {
+        assert_eq!(
+            Model::max_output_tokens_for("claude-3-7-sonnet-latest"),
+            Some(64_000)
+        );
+        assert_eq!(
+            Model::max_output_tokens_for("claude-3-opus-20240229"),
+            Some(4096)
+        );
+        assert_eq!(Model::max_output_tokens_for("claude-99"), None);
+    }
+
     #[tokio::test]
     async fn test_list_models() {
         let credentials = Credentials::from_env();
@@ -0,0 +1,250 @@
+//! # Pagination
+//!
+//! List endpoints return results one page at a time, along with `has_more`, `first_id`, and
+//! `last_id` cursors. The [`Paginated`] trait captures that shape so any list response can be
+//! walked page by page without manually feeding `after_id` back into the next request.
+//!
+//! Each list builder exposes an `into_stream()` method built on top of this trait, available
+//! with the default `runtime` feature:
+//!
+//! ```no_run
+//! use anthropic_api::{models::*, Credentials};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let credentials = Credentials::from_env();
+//!
+//!     let mut models = ModelList::builder()
+//!         .credentials(credentials)
+//!         .limit(20u32)
+//!         .into_stream();
+//!
+//!     while let Some(model) = models.recv().await {
+//!         println!("{}", model.unwrap().id);
+//!     }
+//! }
+//! ```
+//!
+//! To page manually, [`Cursor`] pairs an ID with the direction to page in, so a `first_id`
+//! can't be sent as an `after_id` by mistake. Each list response has `next_page_builder()`
+//! and `prev_page_builder()` methods that start a builder from the right cursor:
+//!
+//! ```no_run
+//! use anthropic_api::{models::*, Credentials};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let credentials = Credentials::from_env();
+//!
+//!     let mut page = ModelList::builder()
+//!         .credentials(credentials.clone())
+//!         .create()
+//!         .await?;
+//!     while page.has_more {
+//!         let Some(builder) = page.next_page_builder() else { break };
+//!         page = builder.credentials(credentials.clone()).create().await?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{AnthropicErrorResponse, ApiResponseOrError};
+use std::future::Future;
+#[cfg(feature = "runtime")]
+use tokio::sync::mpsc::{channel, Receiver};
+
+/// Largest page size accepted by the list endpoints.
+pub const MAX_LIMIT: u32 = 1000;
+
+/// Checks that a list request's page `limit` is between 1 and [`MAX_LIMIT`].
+///
+/// List requests are checked before they are sent, so an out-of-range limit fails fast with
+/// a `validation` error instead of a 400 from the API.
+pub(crate) fn validate_limit(limit: Option<u32>) -> ApiResponseOrError<()> {
+    match limit {
+        Some(limit) if !(1..=MAX_LIMIT).contains(&limit) => Err(AnthropicErrorResponse::new(
+            format!("limit must be between 1 and {MAX_LIMIT}, got {limit}"),
+            "validation".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A position in a list, together with the direction to page in from it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Cursor {
+    /// Fetch the items after this ID, sent as `after_id`
+    After(String),
+    /// Fetch the items before this ID, sent as `before_id`
+    Before(String),
+}
+
+/// A page of results returned by a list endpoint.
+///
+/// Implemented for every list response type (`ModelList`, `UserList`, `WorkspaceList`,
+/// `InviteList`, `ApiKeyList`, and `WorkspaceMemberList`) and for the usage and cost reports.
+pub trait Paginated: Sized + Send + 'static {
+    /// The type of item contained in each page.
+    type Item: Send + 'static;
+    /// The request used to fetch a page.
+    type Request: Clone + Send + 'static;
+
+    /// Fetches a single page for the given request.
+    fn fetch_page(request: Self::Request) -> impl Future<Output = ApiResponseOrError<Self>> + Send;
+
+    /// Returns the request for the page following this one, or `None` if this is the last page.
+    ///
+    /// List endpoints continue in the direction of the original request: forwards using
+    /// `after_id` by default, or backwards using `before_id` if the request set one. Reports
+    /// continue with the `next_page` token. All other request parameters, including the
+    /// credentials and the page limit, are kept.
+    fn next_page_request(&self, request: Self::Request) -> Option<Self::Request>;
+
+    /// Consumes the page and returns its items.
+    fn into_items(self) -> Vec<Self::Item>;
+
+    /// Fetches every page for the request, sending each item to the returned channel.
+    ///
+    /// Pages are fetched until `has_more` is false. If a page fails to load, the error is sent
+    /// as the final item before the channel closes. Dropping the receiver stops pagination.
+    #[cfg(feature = "runtime")]
+    fn paginate(request: Self::Request) -> Receiver<ApiResponseOrError<Self::Item>> {
+        let (tx, rx) = channel(32);
+        tokio::spawn(async move {
+            let mut next = Some(request);
+            while let Some(request) = next.take() {
+                let page = match Self::fetch_page(request.clone()).await {
+                    Ok(page) => page,
+                    Err(error) => {
+                        let _ = tx.send(Err(error)).await;
+                        return;
+                    }
+                };
+                next = page.next_page_request(request);
+                for item in page.into_items() {
+                    if tx.send(Ok(item)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        rx
+    }
+}
+
+/// Implements [`Paginated`] for a list response with the standard `data`, `has_more`,
+/// `first_id`, and `last_id` fields, along with its [`Cursor`] accessors and the `cursor`
+/// setters on the request and its builder. The builder also gets `query_param` for the
+/// request's `extra_query`.
+macro_rules! impl_paginated {
+    ($list:ty, $item:ty, $request:ty, $builder:ty) => {
+        impl $list {
+            /// Returns the cursor for the items after this page, or `None` if the page is empty.
+            ///
+            /// `has_more` only reports whether more items exist in the direction this page was
+            /// requested in, so check it before following the cursor in that direction.
+            pub fn next_page_cursor(&self) -> Option<$crate::pagination::Cursor> {
+                self.last_id.clone().map($crate::pagination::Cursor::After)
+            }
+
+            /// Returns the cursor for the items before this page, or `None` if the page is empty.
+            pub fn prev_page_cursor(&self) -> Option<$crate::pagination::Cursor> {
+                self.first_id
+                    .clone()
+                    .map($crate::pagination::Cursor::Before)
+            }
+
+            /// Returns a builder for the items after this page, or `None` if the page is empty.
+            ///
+            /// Only the cursor is set; the credentials, limit, and any filters must be set again.
+            pub fn next_page_builder(&self) -> Option<$builder> {
+                self.next_page_cursor()
+                    .map(|cursor| <$builder>::default().cursor(cursor))
+            }
+
+            /// Returns a builder for the items before this page, or `None` if the page is empty.
+            ///
+            /// Only the cursor is set; the credentials, limit, and any filters must be set again.
+            pub fn prev_page_builder(&self) -> Option<$builder> {
+                self.prev_page_cursor()
+                    .map(|cursor| <$builder>::default().cursor(cursor))
+            }
+        }
+
+        impl $request {
+            /// Sets `after_id` or `before_id` from the cursor, clearing the other.
+            pub fn set_cursor(&mut self, cursor: $crate::pagination::Cursor) {
+                match cursor {
+                    $crate::pagination::Cursor::After(id) => {
+                        self.after_id = Some(id);
+                        self.before_id = None;
+                    }
+                    $crate::pagination::Cursor::Before(id) => {
+                        self.before_id = Some(id);
+                        self.after_id = None;
+                    }
+                }
+            }
+        }
+
+        impl $builder {
+            /// Adds a query parameter that is sent as-is, for filters the API supports but
+            /// this crate does not model yet.
+            pub fn query_param(
+                mut self,
+                name: impl Into<String>,
+                value: impl Into<String>,
+            ) -> Self {
+                self.extra_query
+                    .get_or_insert_with(Vec::new)
+                    .push((name.into(), value.into()));
+                self
+            }
+
+            /// Starts the page at the cursor, replacing any `after_id` or `before_id` already set.
+            pub fn cursor(mut self, cursor: $crate::pagination::Cursor) -> Self {
+                match cursor {
+                    $crate::pagination::Cursor::After(id) => {
+                        self.after_id = Some(Some(id));
+                        self.before_id = None;
+                    }
+                    $crate::pagination::Cursor::Before(id) => {
+                        self.before_id = Some(Some(id));
+                        self.after_id = None;
+                    }
+                }
+                self
+            }
+        }
+
+        impl $crate::pagination::Paginated for $list {
+            type Item = $item;
+            type Request = $request;
+
+            fn fetch_page(
+                request: Self::Request,
+            ) -> impl ::std::future::Future<Output = $crate::ApiResponseOrError<Self>> + Send {
+                <$list>::create(request)
+            }
+
+            fn next_page_request(&self, mut request: Self::Request) -> Option<Self::Request> {
+                if !self.has_more {
+                    return None;
+                }
+                let cursor = if request.before_id.is_some() {
+                    self.prev_page_cursor()?
+                } else {
+                    self.next_page_cursor()?
+                };
+                request.set_cursor(cursor);
+                Some(request)
+            }
+
+            fn into_items(self) -> Vec<Self::Item> {
+                self.data
+            }
+        }
+    };
+}
+
+pub(crate) use impl_paginated;
@@ -0,0 +1,201 @@
+//! # Incremental Tool Input Parsing
+//!
+//! Tool inputs are streamed as a series of `input_json_delta` fragments. Rather than buffering
+//! the whole string and parsing it once the content block stops, [`PartialJsonParser`] surfaces
+//! each top-level field of the input object as soon as its value is complete, so callers can
+//! begin acting on early fields while the rest of the input is still arriving. Only the text of
+//! the field currently being received is kept in memory.
+//!
+//! This module is available with the `partial-json` feature.
+//!
+//! ## Basic Usage
+//!
+//! ```
+//! use anthropic_api::partial_json::PartialJsonParser;
+//!
+//! let mut parser = PartialJsonParser::new();
+//!
+//! let fields = parser.push(r#"{"path": "src/main.rs", "conte"#).unwrap();
+//! assert_eq!(fields[0].0, "path");
+//! assert!(!parser.is_complete());
+//!
+//! let fields = parser.push(r#"nts": "fn main() {}"}"#).unwrap();
+//! assert_eq!(fields[0].0, "contents");
+//! assert!(parser.is_complete());
+//! ```
+
+use crate::{AnthropicErrorResponse, ApiResponseOrError};
+use serde_json::{Map, Value};
+
+/// Incrementally parses a streamed JSON object, exposing top-level fields as they complete.
+///
+/// A field is complete once the `,` or `}` following its value has been received.
+#[derive(Debug, Clone, Default)]
+pub struct PartialJsonParser {
+    /// Unconsumed text of the field currently being received.
+    pending: String,
+    /// How far into `pending` has already been scanned.
+    scanned: usize,
+    /// Nesting depth; 1 while inside the top-level object.
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    complete: bool,
+    fields: Map<String, Value>,
+}
+
+impl PartialJsonParser {
+    /// Creates a parser that has not received any input yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next fragment of JSON and returns the top-level fields it completed, in order.
+    ///
+    /// Returns a `json_parse_error` if the input is not a valid JSON object.
+    pub fn push(&mut self, fragment: &str) -> ApiResponseOrError<Vec<(String, Value)>> {
+        self.pending.push_str(fragment);
+        let mut completed = Vec::new();
+        let mut i = self.scanned;
+
+        while i < self.pending.len() {
+            let byte = self.pending.as_bytes()[i];
+            i += 1;
+
+            if self.complete || self.depth == 0 {
+                match byte {
+                    b'{' if !self.complete => {
+                        self.depth = 1;
+                        self.pending.drain(..i);
+                        i = 0;
+                    }
+                    byte if byte.is_ascii_whitespace() => {}
+                    _ => return Err(parse_error("expected a JSON object")),
+                }
+                continue;
+            }
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' if self.depth > 1 => self.depth -= 1,
+                b',' | b'}' if self.depth == 1 => {
+                    let member = self.pending[..i - 1].trim();
+                    // Only an empty object may close without any members.
+                    if byte == b',' || !member.is_empty() || !self.fields.is_empty() {
+                        let field = parse_member(member)?;
+                        self.fields.insert(field.0.clone(), field.1.clone());
+                        completed.push(field);
+                    }
+                    if byte == b'}' {
+                        self.depth = 0;
+                        self.complete = true;
+                    }
+                    self.pending.drain(..i);
+                    i = 0;
+                }
+                _ => {}
+            }
+        }
+
+        self.scanned = i;
+        Ok(completed)
+    }
+
+    /// Returns the fields completed so far.
+    pub fn fields(&self) -> &Map<String, Value> {
+        &self.fields
+    }
+
+    /// Returns the value of a completed top-level field.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.get(key)
+    }
+
+    /// Returns true once the closing brace of the object has been received.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Returns the full object, or `None` if it has not been completely received.
+    pub fn into_value(self) -> Option<Value> {
+        self.complete.then_some(Value::Object(self.fields))
+    }
+}
+
+/// Parses a single `"key": value` member of an object.
+fn parse_member(member: &str) -> ApiResponseOrError<(String, Value)> {
+    let object: Map<String, Value> = serde_json::from_str(&format!("{{{}}}", member))
+        .map_err(|e| parse_error(&e.to_string()))?;
+    match object.into_iter().next() {
+        Some(field) => Ok(field),
+        None => Err(parse_error("expected an object member")),
+    }
+}
+
+fn parse_error(message: &str) -> AnthropicErrorResponse {
+    AnthropicErrorResponse::new(
+        format!("Failed to parse streamed tool input: {}", message),
+        "json_parse_error".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_early_fields_exposed_before_object_closes() {
+        let contents = "x,}\"{".repeat(2000);
+        let text = format!(
+            r#"{{"operation": "write", "path": "notes/big.txt", "options": {{"overwrite": true, "modes": ["a", "b}}"]}}, "contents": {}, "count": 42}}"#,
+            serde_json::to_string(&contents).unwrap()
+        );
+        let input: Value = serde_json::from_str(&text).unwrap();
+
+        let mut parser = PartialJsonParser::new();
+        let mut seen = Vec::new();
+        let mut path_seen_at = None;
+        for (offset, chunk) in text.as_bytes().chunks(7).enumerate() {
+            let chunk = std::str::from_utf8(chunk).unwrap();
+            for (key, _) in parser.push(chunk).unwrap() {
+                if key == "path" {
+                    path_seen_at = Some(offset * 7);
+                }
+                seen.push(key);
+            }
+        }
+
+        // "path" is available long before the large "contents" field finishes arriving.
+        assert!(path_seen_at.unwrap() < text.len() / 10);
+        assert_eq!(seen.len(), 5);
+        assert!(parser.is_complete());
+        assert_eq!(parser.get("options"), Some(&input["options"]));
+        assert_eq!(parser.into_value(), Some(input));
+    }
+
+    #[test]
+    fn test_empty_and_invalid_objects() {
+        let mut parser = PartialJsonParser::new();
+        assert!(parser.push(" { } ").unwrap().is_empty());
+        assert_eq!(parser.into_value(), Some(json!({})));
+
+        let mut parser = PartialJsonParser::new();
+        let error = parser.push(r#"{"a": 1,, "b": 2}"#).unwrap_err();
+        assert_eq!(error.error.error_type, "json_parse_error");
+
+        assert!(PartialJsonParser::new().push("[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! # Testing Utilities
+//!
+//! A local mock of the Anthropic API for testing code that uses this crate, enabled by the
+//! `test-util` feature. Point [`Credentials`](crate::Credentials) at a [`MockServer`] to
+//! stub responses and inspect the requests that were sent, without an API key or network
+//! access.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! anthropic-api = { version = "0.0.5", features = ["test-util"] }
+//! ```
+//!
+//! ## Example
+//!
+//! ```
+//! use anthropic_api::{messages::*, testing::*, Credentials};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let server = MockServer::start(vec![json_response(
+//!     "200 OK",
+//!     r#"{"id": "msg_1", "model": "claude-3-7-sonnet-20250219", "role": "assistant",
+//!         "content": [{"type": "text", "text": "Hello!"}], "stop_reason": "end_turn",
+//!         "stop_sequence": null, "type": "message",
+//!         "usage": {"input_tokens": 10, "output_tokens": 2}}"#,
+//! )])
+//! .await;
+//!
+//! let response = MessagesBuilder::builder("claude-3-7-sonnet-20250219", [], 1024)
+//!     .user_text("Hi")
+//!     .credentials(Credentials::new("test-key", server.base_url.as_str()))
+//!     .create()
+//!     .await
+//!     .unwrap();
+//!
+//! assert_eq!(response.text(), "Hello!");
+//! assert!(server.requests()[0].starts_with("POST /v1/messages "));
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A minimal local HTTP server that answers each connection with the next canned response
+/// and records the raw requests it receives.
+pub struct MockServer {
+    /// Base URL to pass to [`Credentials::new`](crate::Credentials::new).
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// Starts a server that replies to successive connections with `responses`, in order.
+    pub async fn start(responses: Vec<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let request = read_request(&mut socket).await;
+                recorded.lock().unwrap().push(request);
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    /// Returns the raw requests received so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Formats a raw HTTP/1.1 response with the given status line, headers, and body.
+pub fn http_response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut response = format!("HTTP/1.1 {status}\r\n");
+    for (name, value) in headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    ));
+    response
+}
+
+/// Formats a raw HTTP/1.1 response carrying a JSON body.
+pub fn json_response(status: &str, body: &str) -> String {
+    http_response(status, &[("content-type", "application/json")], body)
+}
+
+/// Formats a raw HTTP/1.1 server-sent events response with one `data` line per event.
+pub fn sse_response(events: &[&str]) -> String {
+    let body: String = events
+        .iter()
+        .map(|event| format!("data: {}\n\n", event))
+        .collect();
+    http_response("200 OK", &[("content-type", "text/event-stream")], &body)
+}
+
+/// Reads one request, headers and body, from the socket.
+async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = socket.read(&mut chunk).await.unwrap_or(0);
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let text = String::from_utf8_lossy(&buffer);
+        if let Some(header_end) = text.find("\r\n\r\n") {
+            let content_length = text[..header_end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if buffer.len() >= header_end + 4 + content_length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&buffer).into_owned()
+}
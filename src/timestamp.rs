@@ -0,0 +1,211 @@
+//! # Timestamps
+//!
+//! The API reports times such as `created_at` and `expires_at` as RFC 3339 strings, which the
+//! response types keep verbatim as [`Timestamp`]. [`parse_rfc3339`] converts them into
+//! [`SystemTime`] for date arithmetic, and the response types expose it through accessors like
+//! `Invite::expires_at_time`, without pulling in a date-time dependency.
+//!
+//! With the `chrono` feature, [`Timestamp`] is `chrono::DateTime<Utc>` instead, so the
+//! timestamp fields are parsed during deserialization:
+//!
+//! ```toml
+//! [dependencies]
+//! anthropic-api = { version = "0.0.5", features = ["chrono"] }
+//! ```
+//!
+//! ## Basic Usage
+//!
+//! ```
+//! use anthropic_api::timestamp::parse_rfc3339;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let expires_at = parse_rfc3339("2024-11-20T23:58:27.427722Z").unwrap();
+//! let created_at = parse_rfc3339("2024-10-30T23:58:27+00:00").unwrap();
+//! assert_eq!(
+//!     expires_at.duration_since(created_at).unwrap(),
+//!     Duration::new(21 * 24 * 60 * 60, 427_722_000)
+//! );
+//! assert!(expires_at > UNIX_EPOCH);
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An RFC 3339 timestamp in a response: the string as the API sent it, or a
+/// `chrono::DateTime<Utc>` with the `chrono` feature.
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp = String;
+
+/// An RFC 3339 timestamp in a response: the string as the API sent it, or a
+/// `chrono::DateTime<Utc>` with the `chrono` feature.
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Converts a [`Timestamp`] into a [`SystemTime`], or `None` if the string is malformed.
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn to_system_time(value: &Timestamp) -> Option<SystemTime> {
+    parse_rfc3339(value)
+}
+
+/// Converts a [`Timestamp`] into a [`SystemTime`].
+#[cfg(feature = "chrono")]
+pub(crate) fn to_system_time(value: &Timestamp) -> Option<SystemTime> {
+    Some(SystemTime::from(*value))
+}
+
+/// Parses an RFC 3339 timestamp such as `2024-10-30T23:58:27.427722Z` into a [`SystemTime`].
+///
+/// Fractional seconds and numeric UTC offsets are supported. Returns `None` if the value is
+/// not a valid RFC 3339 timestamp.
+pub fn parse_rfc3339(value: &str) -> Option<SystemTime> {
+    let bytes = value.as_bytes();
+    // Every valid timestamp is ASCII, so checking that first keeps the slicing on char boundaries.
+    if !value.is_ascii()
+        || bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+
+    let year = digits(&value[0..4])? as i64;
+    let month = digits(&value[5..7])?;
+    let day = digits(&value[8..10])?;
+    let hour = digits(&value[11..13])?;
+    let minute = digits(&value[14..16])?;
+    let second = digits(&value[17..19])?;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &value[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        for (i, digit) in fraction.bytes().take(len.min(9)).enumerate() {
+            nanos += u32::from(digit - b'0') * 10u32.pow(8 - i as u32);
+        }
+        rest = &fraction[len..];
+    }
+
+    let offset = match rest.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let hours = digits(&rest[1..3])?;
+            let minutes = digits(&rest[4..6])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = (hours * 3600 + minutes * 60) as i64;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86_400
+        + (hour * 3600 + minute * 60 + second) as i64
+        - offset;
+    if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::new(seconds as u64, nanos))
+    } else {
+        UNIX_EPOCH
+            .checked_sub(Duration::from_secs(seconds.unsigned_abs()))?
+            .checked_add(Duration::from_nanos(nanos.into()))
+    }
+}
+
+/// Parses a fixed-width run of ASCII digits.
+fn digits(value: &str) -> Option<u32> {
+    if value.bytes().all(|b| b.is_ascii_digit()) {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days between the Unix epoch and the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_timestamp() {
+        let at: Timestamp = serde_json::from_str(r#""2024-10-31T01:58:27.5+02:00""#).unwrap();
+        assert_eq!(at.timestamp(), 1_730_332_707);
+        assert_eq!(to_system_time(&at), parse_rfc3339("2024-10-30T23:58:27.5Z"));
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let at = |secs: u64, nanos: u32| UNIX_EPOCH + Duration::new(secs, nanos);
+
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(UNIX_EPOCH));
+        assert_eq!(
+            parse_rfc3339("2024-10-30T23:58:27.427722Z"),
+            Some(at(1_730_332_707, 427_722_000))
+        );
+        assert_eq!(
+            parse_rfc3339("2024-10-31T01:58:27+02:00"),
+            Some(at(1_730_332_707, 0))
+        );
+        assert_eq!(
+            parse_rfc3339("2024-02-29t12:00:00.5-05:30"),
+            Some(at(1_709_227_800, 500_000_000))
+        );
+        assert_eq!(
+            parse_rfc3339("1969-12-31T23:59:59.25Z"),
+            Some(UNIX_EPOCH - Duration::from_millis(750))
+        );
+
+        for invalid in [
+            "",
+            "2024-10-30",
+            "2024-10-30T23:58:27",
+            "2024-13-01T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-10-30T23:58:0éZ",
+            "2024-10-30T23:58:27Zéé",
+            "2024-10-30T24:00:00Z",
+            "2024-10-30T23:58:27.Z",
+            "2024-10-30T23:58:27+0200",
+            "+024-10-30T23:58:27Z",
+        ] {
+            assert_eq!(parse_rfc3339(invalid), None, "{invalid}");
+        }
+    }
+}